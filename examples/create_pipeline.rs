@@ -1,8 +1,8 @@
 //! examples/create_pipeline.rs
 //! Demonstrates creating a pipeline with a trigger using the create_pipeline mutation.
 
-use helios_client::dsl::{CreatePipelineBuilder, Pipeline, PipelineBuilder};
-use helios_client::{HeliosClientError, HeliosGraphQLClient};
+use helios_client::dsl::{Pipeline, PipelineBuilder};
+use helios_client::{HeliosClientError, HeliosGraphQLClient, Trigger};
 
 #[tokio::main]
 async fn main() -> Result<(), HeliosClientError> {
@@ -16,8 +16,8 @@ async fn main() -> Result<(), HeliosClientError> {
     let graphql_endpoint = "http://localhost:8000/api/graphql";
 
     // 2. Build the pipeline with a trigger.
-    let pipeline = CreatePipelineBuilder::new("test")
-        .trigger("test")
+    let pipeline: Pipeline = PipelineBuilder::new("test")
+        .trigger(Trigger::webhook("/hooks/test", None))
         .webhook("test_webhook", "https://example.com/webhook")
         .then()
         .llm_workflow("test_llm")
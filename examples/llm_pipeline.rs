@@ -41,7 +41,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Ok(response) => {
             println!("\n✅ Pipeline executed successfully!");
             println!("Server Message: {}", response.message);
-            if let Some(output) = response.outputs.first() {
+            if let Some(output) = response.output("drone_flight") {
                 println!("\nOutput from 'drone_flight':");
                 println!(
                     "{}",
@@ -0,0 +1,48 @@
+//! examples/pipeline_file.rs
+//! Demonstrates loading a pipeline definition file and running it.
+use helios_client::dsl::Pipeline;
+use helios_client::HeliosGraphQLClient;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // This example loads `examples/pipelines/llm_pipeline.yaml` (or the file
+    // given as the first argument) and runs it.
+    //
+    // NOTE: This example requires a running Helios server instance accessible
+    // at the specified GraphQL endpoint.
+
+    // 1. Define the GraphQL server endpoint.
+    let graphql_endpoint = "http://localhost:8000/api/graphql";
+
+    // 2. Load the pipeline definition. The format follows the file extension.
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "examples/pipelines/llm_pipeline.yaml".to_string());
+    let pipeline = Pipeline::from_file(&path)?;
+    pipeline.validate()?;
+    println!("Loaded pipeline '{}' from {}", pipeline.name, path);
+
+    // 3. Create the GraphQL Client.
+    let client = HeliosGraphQLClient::new(graphql_endpoint.to_string());
+
+    // 4. Execute the GraphQL Mutation.
+    match client.run_pipeline(pipeline).await {
+        Ok(response) => {
+            println!("\n✅ Pipeline executed successfully!");
+            println!("Server Message: {}", response.message);
+            for output in &response.outputs {
+                println!("  - Step ID: {}", output.step_id);
+                println!("    Result: {}", output.result_json);
+            }
+        }
+        Err(e) => {
+            eprintln!("\n❌ Error running pipeline: {}", e);
+            eprintln!(
+                "\nHint: Is the Helios server running at {}?",
+                graphql_endpoint
+            );
+        }
+    }
+
+    Ok(())
+}
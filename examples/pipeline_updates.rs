@@ -0,0 +1,54 @@
+//! examples/pipeline_updates.rs
+//! Demonstrates following live pipeline progress over a GraphQL subscription.
+use futures_util::StreamExt;
+use helios_client::subscription::PipelineUpdate;
+use helios_client::HeliosGraphQLClient;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // This example subscribes to `pipelineUpdates` and prints every event until
+    // the server closes the subscription. Run any of the other examples in a
+    // second terminal to see steps start and finish.
+    //
+    // NOTE: This example requires a running Helios server instance accessible
+    // at the specified GraphQL endpoint.
+
+    // 1. Define the GraphQL server endpoint.
+    let graphql_endpoint = "http://localhost:8000/api/graphql";
+
+    // 2. Create the GraphQL Client.
+    let client = HeliosGraphQLClient::new(graphql_endpoint.to_string());
+
+    // 3. Open the subscription.
+    let mut updates = match client.subscribe_pipeline_updates().await {
+        Ok(updates) => updates,
+        Err(e) => {
+            eprintln!("\n❌ Error subscribing to pipeline updates: {}", e);
+            eprintln!(
+                "\nHint: Is the Helios server running at {}?",
+                graphql_endpoint
+            );
+            return Ok(());
+        }
+    };
+    println!("Subscribed. Waiting for pipeline updates...");
+
+    // 4. Print events as they arrive.
+    while let Some(update) = updates.next().await {
+        match update? {
+            PipelineUpdate::StepStarted { step_id, .. } => println!("▶ {} started", step_id),
+            PipelineUpdate::StepFinished {
+                step_id,
+                error: Some(error),
+                ..
+            } => println!("✖ {} failed: {}", step_id, error),
+            PipelineUpdate::StepFinished { step_id, .. } => println!("✔ {} finished", step_id),
+            PipelineUpdate::Log {
+                step_id, message, ..
+            } => println!("  [{}] {}", step_id.as_deref().unwrap_or("-"), message),
+            PipelineUpdate::Other(raw) => println!("  {}", raw),
+        }
+    }
+
+    Ok(())
+}
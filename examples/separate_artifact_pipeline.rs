@@ -16,13 +16,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .container("step1", "alpine:latest")
         .working_dir("/app")
         .with_args(&["sh", "-c", "mkdir -p /app && cd /app && echo '=== PWD ==='; pwd; echo '=== BEFORE ==='; ls -l; echo secret > step1.txt; echo '=== AFTER ==='; ls -l; sleep 2"])
-        .with_volume("pvc-step1", "/app")
+        .named_volume("pvc-step1", "/app")
         .then()
         .container("step2", "alpine:latest")
         .depends_on("step1")
         .working_dir("/app")
         .with_args(&["sh", "-c", "cat step1.txt || echo not found"])
-        .with_volume("pvc-step2", "/app")
+        .named_volume("pvc-step2", "/app")
         .then()
         .output("step1")
         .output("step2")
@@ -39,7 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("\n✅ Pipeline executed successfully!");
             println!("Server Message: {}", response.message);
             println!("\nOutputs:");
-            for output in response.outputs {
+            for output in &response.outputs {
                 println!("  - Step ID: {}", output.step_id);
                 println!("    Result: {}", output.result_json);
             }
@@ -41,7 +41,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("\n✅ Pipeline executed successfully!");
             println!("Server Message: {}", response.message);
             println!("\nOutputs:");
-            for output in response.outputs {
+            for output in &response.outputs {
                 println!("  - Step ID: {}", output.step_id);
                 println!("    Result: {}", output.result_json);
             }
@@ -1,5 +1,6 @@
 //! examples/webhook_pipeline.rs
 use helios_client::dsl::PipelineBuilder;
+use helios_client::webhook::HttpMethod;
 use helios_client::HeliosGraphQLClient;
 
 #[tokio::main]
@@ -15,7 +16,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 2. Build the pipeline.
     let pipeline = PipelineBuilder::new("public-api-pipeline")
         .webhook("get_public_apis", "https://api.publicapis.org/entries")
-        .method("GET")
+        .method(HttpMethod::Get)
         .then()
         .output("get_public_apis")
         .build();
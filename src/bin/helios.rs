@@ -0,0 +1,282 @@
+//! `helios` — run, register and inspect Helios pipelines from the shell.
+//!
+//! Exit codes: 0 on success, 1 when a pipeline run reports failure, 2 for
+//! invalid pipeline files, 3 for client or server errors.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use helios_client::dsl::Pipeline;
+use helios_client::run_pipeline::run_pipeline::RunPipelineRunPipeline;
+use helios_client::{HeliosClientError, HeliosGraphQLClient, RetryPolicy};
+use serde_json::{json, Value};
+
+const EXIT_PIPELINE_FAILED: u8 = 1;
+const EXIT_INVALID_PIPELINE: u8 = 2;
+const EXIT_CLIENT_ERROR: u8 = 3;
+
+#[derive(Parser)]
+#[command(
+    name = "helios",
+    version,
+    about = "Run, register and inspect Helios pipelines"
+)]
+struct Cli {
+    /// GraphQL endpoint of the Helios server.
+    #[arg(
+        long,
+        env = "HELIOS_ENDPOINT",
+        default_value = "http://localhost:8000/api/graphql",
+        global = true
+    )]
+    endpoint: String,
+
+    /// Bearer token sent with every request.
+    #[arg(long, env = "HELIOS_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+
+    /// API key sent in the `x-api-key` header.
+    #[arg(long, env = "HELIOS_API_KEY", hide_env_values = true, global = true)]
+    api_key: Option<String>,
+
+    /// Request timeout in seconds.
+    #[arg(long, env = "HELIOS_TIMEOUT", global = true)]
+    timeout: Option<u64>,
+
+    /// Total attempts for transient failures (1 disables retries).
+    #[arg(long, env = "HELIOS_ATTEMPTS", default_value_t = 1, global = true)]
+    attempts: u32,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a pipeline definition file and print its outputs.
+    Run {
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// Register a pipeline with a trigger.
+    Create {
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// Validate pipeline definition files without contacting the server.
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Print the step dependency graph of a pipeline.
+    Graph {
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Text)]
+        format: GraphFormat,
+    },
+    /// Check that the server is reachable.
+    Ping,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Raw,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Text,
+    Dot,
+    Mermaid,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(EXIT_CLIENT_ERROR)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<ExitCode, HeliosClientError> {
+    match &cli.command {
+        Command::Run { file, output } => {
+            let Some(pipeline) = load(file) else {
+                return Ok(ExitCode::from(EXIT_INVALID_PIPELINE));
+            };
+            let response = client(&cli)?.run_pipeline(pipeline).await?;
+            print_run(&response, *output);
+            Ok(if response.is_success() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(EXIT_PIPELINE_FAILED)
+            })
+        }
+        Command::Create { file, output } => {
+            let Some(pipeline) = load(file) else {
+                return Ok(ExitCode::from(EXIT_INVALID_PIPELINE));
+            };
+            let response = client(&cli)?.create_pipeline(pipeline).await?;
+            match output {
+                OutputFormat::Json => {
+                    let value = json!({ "id": response.id, "trigger": response.trigger });
+                    println!("{}", serde_json::to_string_pretty(&value)?);
+                }
+                OutputFormat::Raw => println!("{}", response.id),
+                OutputFormat::Table => {
+                    println!("ID       {}", response.id);
+                    println!("TRIGGER  {}", response.trigger);
+                    println!("STEPS    {}", response.steps.len());
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Validate { files } => {
+            let mut valid = true;
+            for file in files {
+                if load(file).is_some() {
+                    println!("{}: ok", file.display());
+                } else {
+                    valid = false;
+                }
+            }
+            Ok(if valid {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(EXIT_INVALID_PIPELINE)
+            })
+        }
+        Command::Graph { file, format } => {
+            let Some(pipeline) = load(file) else {
+                return Ok(ExitCode::from(EXIT_INVALID_PIPELINE));
+            };
+            match format {
+                GraphFormat::Text => print_graph(&pipeline),
+                GraphFormat::Dot => print!("{}", pipeline.to_dot()),
+                GraphFormat::Mermaid => print!("{}", pipeline.to_mermaid()),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Ping => {
+            let message = client(&cli)?.hello().await?;
+            println!("{} is reachable: {}", cli.endpoint, message);
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn client(cli: &Cli) -> Result<HeliosGraphQLClient, HeliosClientError> {
+    let mut builder = HeliosGraphQLClient::builder(&cli.endpoint);
+    if let Some(token) = &cli.token {
+        builder = builder.bearer_token(token);
+    }
+    if let Some(key) = &cli.api_key {
+        builder = builder.api_key(key);
+    }
+    if let Some(seconds) = cli.timeout {
+        builder = builder.timeout(Duration::from_secs(seconds));
+    }
+    if cli.attempts > 1 {
+        builder = builder.retry_policy(RetryPolicy::new().max_attempts(cli.attempts));
+    }
+    builder.build()
+}
+
+/// Loads and validates a pipeline file, reporting problems and warnings on
+/// stderr.
+fn load(path: &Path) -> Option<Pipeline> {
+    let pipeline = match Pipeline::from_file(path) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if let Err(errors) = pipeline.validate() {
+        eprintln!("{}: {}", path.display(), errors);
+        return None;
+    }
+    for warning in pipeline.warnings() {
+        eprintln!("{}: warning: {}", path.display(), warning);
+    }
+    Some(pipeline)
+}
+
+fn print_run(response: &RunPipelineRunPipeline, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            let outputs: Vec<Value> = response
+                .outputs
+                .iter()
+                .map(|o| json!({ "stepId": o.step_id, "resultJson": o.result_json, "error": o.error }))
+                .collect();
+            let value = json!({
+                "success": response.success,
+                "message": response.message,
+                "outputs": outputs,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
+            );
+        }
+        OutputFormat::Raw => {
+            for output in &response.outputs {
+                match &output.result_json {
+                    Value::String(s) => println!("{}", s),
+                    other => println!("{}", other),
+                }
+            }
+        }
+        OutputFormat::Table => {
+            let width = response
+                .outputs
+                .iter()
+                .map(|o| o.step_id.len())
+                .chain(std::iter::once("STEP".len()))
+                .max()
+                .unwrap_or_default();
+            println!("{:<width$}  {:<6}  RESULT", "STEP", "STATUS");
+            for output in &response.outputs {
+                let (status, result) = match &output.error {
+                    Some(error) => ("error", error.clone()),
+                    None => ("ok", output.result_json.to_string()),
+                };
+                println!("{:<width$}  {:<6}  {}", output.step_id, status, result);
+            }
+            eprintln!(
+                "\n{} {}",
+                if response.success { "✅" } else { "❌" },
+                response.message
+            );
+        }
+    }
+}
+
+fn print_graph(pipeline: &Pipeline) {
+    println!("{}", pipeline.name);
+    for step in &pipeline.steps {
+        let marker = if pipeline.outputs.iter().any(|o| o == step.effective_id()) {
+            " [output]"
+        } else {
+            ""
+        };
+        println!("  {} ({}){}", step.effective_id(), step.type_, marker);
+        for dependency in &step.depends_on {
+            println!("    <- {}", dependency);
+        }
+        if let Some(expression) = &step.expression {
+            println!("    when {}", expression);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, USER_AGENT};
+use reqwest::{Certificate, Client, Identity, Proxy};
+
+use crate::{HeliosClientError, HeliosGraphQLClient, RetryPolicy};
+
+/// Header used by [`HeliosClientBuilder::api_key`] when no header is given.
+pub const DEFAULT_API_KEY_HEADER: &str = "x-api-key";
+
+/// Credentials attached to every request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+    /// An API key sent in the given header.
+    ApiKey { header: String, key: String },
+}
+
+impl Auth {
+    fn header(&self) -> Result<(HeaderName, HeaderValue), HeliosClientError> {
+        let (name, value) = match self {
+            Auth::Bearer(token) if token.is_empty() => {
+                return Err(HeliosClientError::InvalidConfig(
+                    "bearer token is empty".to_string(),
+                ))
+            }
+            Auth::ApiKey { key, .. } if key.is_empty() => {
+                return Err(HeliosClientError::InvalidConfig(
+                    "API key is empty".to_string(),
+                ))
+            }
+            Auth::Bearer(token) => (AUTHORIZATION, format!("Bearer {}", token)),
+            Auth::ApiKey { header, key } => (parse_header_name(header)?, key.clone()),
+        };
+        let mut value = parse_header_value(name.as_str(), &value)?;
+        value.set_sensitive(true);
+        Ok((name, value))
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Auth::ApiKey { header, .. } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+/// Configures a [`HeliosGraphQLClient`].
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use helios_client::HeliosGraphQLClient;
+///
+/// let client = HeliosGraphQLClient::builder("https://helios.example.com/api/graphql")
+///     .bearer_token("my-token")
+///     .timeout(Duration::from_secs(30))
+///     .header("x-team", "platform")
+///     .build()?;
+/// # Ok::<(), helios_client::HeliosClientError>(())
+/// ```
+///
+/// Proxy and custom TLS settings apply to HTTP requests; subscriptions reuse
+/// the auth, user agent and default headers.
+#[derive(Debug, Clone)]
+pub struct HeliosClientBuilder {
+    endpoint: String,
+    auth: Option<Auth>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: String,
+    headers: Vec<(String, String)>,
+    proxy: Option<String>,
+    no_proxy: bool,
+    root_certificates_pem: Vec<Vec<u8>>,
+    identity_pem: Option<Vec<u8>>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl HeliosClientBuilder {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            auth: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string(),
+            headers: Vec::new(),
+            proxy: None,
+            no_proxy: false,
+            root_certificates_pem: Vec::new(),
+            identity_pem: None,
+            retry_policy: None,
+        }
+    }
+
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn bearer_token(self, token: &str) -> Self {
+        self.auth(Auth::Bearer(token.to_string()))
+    }
+
+    /// Sends `key` in the [`DEFAULT_API_KEY_HEADER`] header.
+    pub fn api_key(self, key: &str) -> Self {
+        self.api_key_header(DEFAULT_API_KEY_HEADER, key)
+    }
+
+    pub fn api_key_header(self, header: &str, key: &str) -> Self {
+        self.auth(Auth::ApiKey {
+            header: header.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    /// Total time allowed for each request, from connecting until the
+    /// response body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Adds a header sent with every request. Later values for the same
+    /// header replace earlier ones.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Routes all requests through the given proxy URL.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Ignores proxy settings from the environment (`HTTPS_PROXY` etc.).
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Trusts an additional PEM-encoded root certificate.
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        self.root_certificates_pem.push(pem.to_vec());
+        self
+    }
+
+    /// Presents a client certificate for mutual TLS. `pem` must contain the
+    /// certificate chain and the private key.
+    pub fn identity_pem(mut self, pem: &[u8]) -> Self {
+        self.identity_pem = Some(pem.to_vec());
+        self
+    }
+
+    /// Retries transient failures according to `policy`. Without a policy
+    /// every request is attempted once.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    pub fn build(self) -> Result<HeliosGraphQLClient, HeliosClientError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            parse_header_value(USER_AGENT.as_str(), &self.user_agent)?,
+        );
+        for (name, value) in &self.headers {
+            headers.insert(parse_header_name(name)?, parse_header_value(name, value)?);
+        }
+        if let Some(auth) = &self.auth {
+            let (name, value) = auth.header()?;
+            headers.insert(name, value);
+        }
+
+        let mut http_client = Client::builder().default_headers(headers.clone());
+        if let Some(timeout) = self.timeout {
+            http_client = http_client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http_client = http_client.connect_timeout(timeout);
+        }
+        if self.no_proxy {
+            http_client = http_client.no_proxy();
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy).map_err(|e| {
+                HeliosClientError::InvalidConfig(format!("invalid proxy `{}`: {}", proxy, e))
+            })?;
+            http_client = http_client.proxy(proxy);
+        }
+        for pem in &self.root_certificates_pem {
+            let certificate = Certificate::from_pem(pem).map_err(|e| {
+                HeliosClientError::InvalidConfig(format!("invalid root certificate: {}", e))
+            })?;
+            http_client = http_client.add_root_certificate(certificate);
+        }
+        if let Some(pem) = &self.identity_pem {
+            let identity = Identity::from_pem(pem).map_err(|e| {
+                HeliosClientError::InvalidConfig(format!("invalid client identity: {}", e))
+            })?;
+            http_client = http_client.identity(identity);
+        }
+
+        Ok(HeliosGraphQLClient {
+            endpoint: self.endpoint,
+            http_client: http_client.build()?,
+            headers,
+            connect_timeout: self.connect_timeout,
+            retry_policy: self.retry_policy,
+        })
+    }
+}
+
+fn parse_header_name(name: &str) -> Result<HeaderName, HeliosClientError> {
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| HeliosClientError::InvalidConfig(format!("invalid header name `{}`", name)))
+}
+
+fn parse_header_value(name: &str, value: &str) -> Result<HeaderValue, HeliosClientError> {
+    HeaderValue::from_str(value).map_err(|_| {
+        HeliosClientError::InvalidConfig(format!("invalid value for header `{}`", name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: &str = "http://localhost:4000/graphql";
+
+    fn config_error(builder: HeliosClientBuilder) -> String {
+        match builder.build() {
+            Err(HeliosClientError::InvalidConfig(message)) => message,
+            other => panic!("expected InvalidConfig, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn bearer_tokens_set_a_sensitive_authorization_header() {
+        let client = HeliosClientBuilder::new(ENDPOINT)
+            .bearer_token("tok")
+            .build()
+            .unwrap();
+
+        let value = &client.headers[AUTHORIZATION];
+        assert_eq!(value, "Bearer tok");
+        assert!(value.is_sensitive());
+    }
+
+    #[test]
+    fn api_keys_use_the_default_or_given_header() {
+        let client = HeliosClientBuilder::new(ENDPOINT)
+            .api_key("key")
+            .build()
+            .unwrap();
+        assert_eq!(client.headers[DEFAULT_API_KEY_HEADER], "key");
+        assert!(client.headers[DEFAULT_API_KEY_HEADER].is_sensitive());
+        assert!(!client.headers.contains_key(AUTHORIZATION));
+
+        let client = HeliosClientBuilder::new(ENDPOINT)
+            .api_key_header("x-gateway-key", "key")
+            .build()
+            .unwrap();
+        assert_eq!(client.headers["x-gateway-key"], "key");
+        assert!(!client.headers.contains_key(DEFAULT_API_KEY_HEADER));
+    }
+
+    #[test]
+    fn later_headers_replace_earlier_ones_and_auth_wins() {
+        let client = HeliosClientBuilder::new(ENDPOINT)
+            .user_agent("helios-tests")
+            .header("x-team", "a")
+            .header("X-Team", "b")
+            .header("authorization", "Basic abc")
+            .bearer_token("tok")
+            .build()
+            .unwrap();
+
+        assert_eq!(client.headers[USER_AGENT], "helios-tests");
+        assert_eq!(client.headers["x-team"], "b");
+        assert_eq!(client.headers[AUTHORIZATION], "Bearer tok");
+    }
+
+    #[test]
+    fn empty_credentials_are_rejected() {
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).api_key(""));
+        assert_eq!(message, "API key is empty");
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).bearer_token(""));
+        assert_eq!(message, "bearer token is empty");
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).header("bad header", "v"));
+        assert_eq!(message, "invalid header name `bad header`");
+
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).header("x-team", "a\nb"));
+        assert_eq!(message, "invalid value for header `x-team`");
+
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).api_key_header("", "key"));
+        assert_eq!(message, "invalid header name ``");
+
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).user_agent("a\rb"));
+        assert_eq!(message, "invalid value for header `user-agent`");
+    }
+
+    #[test]
+    fn invalid_proxies_are_rejected() {
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).proxy("http://[::1"));
+        assert!(
+            message.starts_with("invalid proxy `http://[::1`"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn invalid_pem_is_rejected() {
+        let pem = b"-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----\n";
+
+        let message =
+            config_error(HeliosClientBuilder::new(ENDPOINT).add_root_certificate_pem(pem));
+        assert!(message.starts_with("invalid root certificate"), "{message}");
+
+        let message = config_error(HeliosClientBuilder::new(ENDPOINT).identity_pem(b"garbage"));
+        assert!(message.starts_with("invalid client identity"), "{message}");
+    }
+
+    #[test]
+    fn debug_output_redacts_credentials() {
+        let debug = format!(
+            "{:?}",
+            HeliosClientBuilder::new(ENDPOINT).api_key_header("x-gateway-key", "secret-key")
+        );
+        assert!(debug.contains("x-gateway-key"));
+        assert!(!debug.contains("secret-key"));
+        assert_eq!(
+            format!("{:?}", Auth::Bearer("tok".to_string())),
+            "Bearer(<redacted>)"
+        );
+    }
+}
@@ -11,3 +11,5 @@ pub type CreatePipelineVariables = crate::create_pipeline::create_pipeline::Vari
 pub type CreatePipelineResponseData = crate::create_pipeline::create_pipeline::ResponseData;
 pub type CreatePipelineCreatePipeline =
     crate::create_pipeline::create_pipeline::CreatePipelineCreatePipeline;
+
+pub type HelloResponseData = crate::hello::hello::ResponseData;
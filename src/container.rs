@@ -0,0 +1,297 @@
+//! Typed configuration for `nimbus_container` steps.
+//!
+//! [`ContainerSpec`] mirrors the step's `data` object, so it can be read from
+//! and written back to an existing [`Step`](crate::dsl::Step) without losing
+//! keys it does not model.
+//!
+//! Registry passwords are never stored in the step: [`RegistryCredentials`]
+//! names a secret the server resolves when it pulls the image.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// When the server pulls the image before running the step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullPolicy {
+    Always,
+    IfNotPresent,
+    Never,
+}
+
+/// CPU and memory caps for the container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPU cores; fractions such as `0.5` are allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+}
+
+/// Credentials for pulling from a private registry, with the password read
+/// from the named server-side secret `password_secret`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryCredentials {
+    pub registry: String,
+    pub username: String,
+    pub password_secret: String,
+}
+
+impl RegistryCredentials {
+    pub fn new(registry: &str, username: &str, password_secret: &str) -> Self {
+        Self {
+            registry: registry.to_string(),
+            username: username.to_string(),
+            password_secret: password_secret.to_string(),
+        }
+    }
+}
+
+/// Where a mounted volume comes from. Serialized by key: `host_path`,
+/// `volume_name` or `scratch`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VolumeSource {
+    /// A directory on the host, bind-mounted.
+    Host { host_path: String },
+    /// A named volume (e.g. a persistent volume claim) that outlives the step.
+    Named { volume_name: String },
+    /// An empty volume created for the step and discarded afterwards.
+    Scratch { scratch: ScratchVolume },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScratchVolume {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_limit_bytes: Option<u64>,
+}
+
+/// A volume mounted into the container at `container_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeMount {
+    #[serde(flatten)]
+    pub source: VolumeSource,
+    pub container_path: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl VolumeMount {
+    /// Bind-mounts `host_path` from the host.
+    pub fn host(host_path: &str, container_path: &str) -> Self {
+        Self::new(
+            VolumeSource::Host {
+                host_path: host_path.to_string(),
+            },
+            container_path,
+        )
+    }
+
+    /// Mounts the named volume `name`.
+    pub fn named(name: &str, container_path: &str) -> Self {
+        Self::new(
+            VolumeSource::Named {
+                volume_name: name.to_string(),
+            },
+            container_path,
+        )
+    }
+
+    /// Mounts an empty scratch volume.
+    pub fn scratch(container_path: &str) -> Self {
+        Self::new(
+            VolumeSource::Scratch {
+                scratch: ScratchVolume::default(),
+            },
+            container_path,
+        )
+    }
+
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Caps the size of a scratch volume; has no effect on other sources.
+    pub fn size_limit(mut self, bytes: u64) -> Self {
+        if let VolumeSource::Scratch { scratch } = &mut self.source {
+            scratch.size_limit_bytes = Some(bytes);
+        }
+        self
+    }
+
+    fn new(source: VolumeSource, container_path: &str) -> Self {
+        Self {
+            source,
+            container_path: container_path.to_string(),
+            read_only: false,
+        }
+    }
+}
+
+impl fmt::Display for VolumeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VolumeSource::Host { host_path } => write!(f, "host path `{}`", host_path),
+            VolumeSource::Named { volume_name } => write!(f, "volume `{}`", volume_name),
+            VolumeSource::Scratch { .. } => f.write_str("scratch volume"),
+        }
+    }
+}
+
+/// The `data` of a `nimbus_container` step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerSpec {
+    pub image: String,
+    /// Id of the step the container runs for; set by the builder.
+    #[serde(default)]
+    pub step_id: String,
+    /// Arguments passed to the entrypoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Overrides the image entrypoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<VolumeMount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    /// Timeout in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceLimits>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_policy: Option<PullPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_auth: Option<RegistryCredentials>,
+    /// Keys not modelled above, kept as-is.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ContainerSpec {
+    pub fn new(image: &str, step_id: &str) -> Self {
+        Self {
+            image: image.to_string(),
+            step_id: step_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Reads a spec from a step's `data`.
+    pub fn from_data(data: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(data)
+    }
+
+    /// The `data` object for this spec.
+    pub fn to_data(&self) -> Value {
+        serde_json::to_value(self).expect("container spec serializes to JSON")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::PipelineBuilder;
+    use serde_json::json;
+
+    #[test]
+    fn builder_options_serialize_into_step_data() {
+        let pipeline = PipelineBuilder::new("build")
+            .container("compile", "registry.example.com/rust:1.80")
+            .with_args(&["cargo", "build"])
+            .entrypoint(&["/bin/sh", "-c"])
+            .env("RUST_LOG", "debug")
+            .working_dir("/work")
+            .timeout(600)
+            .cpu_limit(1.5)
+            .memory_limit(2 * 1024 * 1024 * 1024)
+            .pull_policy(PullPolicy::IfNotPresent)
+            .then()
+            .build();
+
+        assert_eq!(
+            pipeline.steps[0].data,
+            json!({
+                "image": "registry.example.com/rust:1.80",
+                "step_id": "compile",
+                "command": ["cargo", "build"],
+                "entrypoint": ["/bin/sh", "-c"],
+                "env": { "RUST_LOG": "debug" },
+                "working_dir": "/work",
+                "timeout": 600,
+                "resources": { "cpus": 1.5, "memory_bytes": 2147483648u64 },
+                "pull_policy": "if_not_present",
+            })
+        );
+    }
+
+    #[test]
+    fn registry_credentials_name_a_password_secret() {
+        let pipeline = PipelineBuilder::new("build")
+            .container("compile", "registry.example.com/rust:1.80")
+            .registry_credentials(RegistryCredentials::new(
+                "registry.example.com",
+                "ci",
+                "registry_password",
+            ))
+            .then()
+            .build();
+
+        assert_eq!(
+            pipeline.steps[0].data["registry_auth"],
+            json!({
+                "registry": "registry.example.com",
+                "username": "ci",
+                "password_secret": "registry_password",
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_keys_survive_a_round_trip() {
+        let data = json!({ "image": "alpine", "step_id": "s", "env": { "A": "1" }, "gpu": true });
+
+        let spec = ContainerSpec::from_data(&data).unwrap();
+
+        assert_eq!(spec.env["A"], "1");
+        assert_eq!(spec.to_data(), data);
+    }
+
+    #[test]
+    fn volumes_serialize_by_source() {
+        let pipeline = PipelineBuilder::new("artifacts")
+            .container("write", "alpine")
+            .named_volume("pvc-artifacts", "/out")
+            .bind_mount("/etc/ssl", "/certs")
+            .mount(VolumeMount::scratch("/tmp/work").size_limit(1024))
+            .mount(VolumeMount::named("cache", "/cache").read_only())
+            .then()
+            .build();
+
+        let data = &pipeline.steps[0].data;
+        assert_eq!(
+            data["volumes"],
+            json!([
+                { "volume_name": "pvc-artifacts", "container_path": "/out", "read_only": false },
+                { "host_path": "/etc/ssl", "container_path": "/certs", "read_only": false },
+                { "scratch": { "size_limit_bytes": 1024 }, "container_path": "/tmp/work", "read_only": false },
+                { "volume_name": "cache", "container_path": "/cache", "read_only": true },
+            ])
+        );
+        let spec = ContainerSpec::from_data(data).unwrap();
+        assert_eq!(
+            spec.volumes[2],
+            VolumeMount::scratch("/tmp/work").size_limit(1024)
+        );
+    }
+}
@@ -34,7 +34,7 @@ pub mod create_pipeline {
     type ID = String;
     type JSON = super::JSON;
 
-    #[derive()]
+    #[derive(Debug)]
     pub enum ActionType {
         ACTION_TYPE_UNSPECIFIED,
         WEBHOOK,
@@ -91,6 +91,8 @@ pub mod create_pipeline {
         pub target: Option<String>,
         #[serde(rename = "dependsOn")]
         pub depends_on: Option<String>,
+        #[serde(rename = "dependsOnAll", skip_serializing_if = "Option::is_none")]
+        pub depends_on_all: Option<Vec<String>>,
         pub expression: Option<String>,
     }
 
@@ -101,20 +103,20 @@ pub mod create_pipeline {
 
     impl Variables {}
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct ResponseData {
         #[serde(rename = "createPipeline")]
         pub create_pipeline: CreatePipelineCreatePipeline,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct CreatePipelineCreatePipeline {
         pub id: String,
         pub trigger: String,
         pub steps: Vec<CreatePipelineCreatePipelineSteps>,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct CreatePipelineCreatePipelineSteps {
         #[serde(rename = "type")]
         pub type_: ActionType,
@@ -0,0 +1,200 @@
+//! Pipeline diagrams.
+//!
+//! [`Pipeline::to_dot`] and [`Pipeline::to_mermaid`] draw one node per step,
+//! labelled with its id, [`ActionType`] and main setting (image, URL, event
+//! topic or workflow type), and one edge per `depends_on` entry, pointing
+//! from the dependency to the dependent step. Edges into a step with a
+//! `when` expression are dashed and labelled with it, and declared outputs
+//! are highlighted.
+
+use std::fmt::Write;
+
+use crate::dsl::{ActionType, Pipeline, Step};
+
+impl Pipeline {
+    /// Renders the pipeline as a Graphviz `digraph`.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph {} {{", dot_quote(&self.name));
+        out.push_str("  rankdir=LR;\n");
+        out.push_str("  node [shape=box, style=rounded];\n");
+        for step in &self.steps {
+            let id = step.effective_id();
+            let label = node_label(step).join("\n");
+            let _ = write!(out, "  {} [label={}", dot_quote(id), dot_quote(&label));
+            if self.is_output(id) {
+                out.push_str(", style=\"rounded,filled,bold\", fillcolor=\"#fff2cc\"");
+            }
+            out.push_str("];\n");
+        }
+        for step in &self.steps {
+            for dependency in &step.depends_on {
+                let _ = write!(
+                    out,
+                    "  {} -> {}",
+                    dot_quote(dependency),
+                    dot_quote(step.effective_id())
+                );
+                if let Some(expression) = &step.expression {
+                    let _ = write!(out, " [style=dashed, label={}]", dot_quote(expression));
+                }
+                out.push_str(";\n");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the pipeline as a Mermaid `flowchart`.
+    pub fn to_mermaid(&self) -> String {
+        // Mermaid node ids must be plain identifiers, so steps are numbered
+        // and their ids only appear in labels.
+        let node = |id: &str| -> String {
+            match self.steps.iter().position(|s| s.effective_id() == id) {
+                Some(index) => format!("s{}", index),
+                None => format!("missing_{}", mermaid_identifier(id)),
+            }
+        };
+
+        let mut out = String::from("flowchart LR\n");
+        for (index, step) in self.steps.iter().enumerate() {
+            let label = node_label(step)
+                .iter()
+                .map(|line| mermaid_escape(line))
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            let _ = writeln!(out, "    s{}[\"{}\"]", index, label);
+        }
+        for step in &self.steps {
+            let target = node(step.effective_id());
+            for dependency in &step.depends_on {
+                let source = node(dependency);
+                match &step.expression {
+                    Some(expression) => {
+                        let _ = writeln!(
+                            out,
+                            "    {} -.->|\"{}\"| {}",
+                            source,
+                            mermaid_escape(expression),
+                            target
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(out, "    {} --> {}", source, target);
+                    }
+                }
+            }
+        }
+        let outputs: Vec<String> = self
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| self.is_output(step.effective_id()))
+            .map(|(index, _)| format!("s{}", index))
+            .collect();
+        if !outputs.is_empty() {
+            out.push_str("    classDef output fill:#fff2cc,stroke:#d6b656,stroke-width:2px\n");
+            let _ = writeln!(out, "    class {} output", outputs.join(","));
+        }
+        out
+    }
+
+    fn is_output(&self, id: &str) -> bool {
+        self.outputs.iter().any(|output| output == id)
+    }
+}
+
+/// Label lines for a step: id, action type, and the setting that best
+/// identifies what it does.
+fn node_label(step: &Step) -> Vec<String> {
+    let mut lines = vec![step.effective_id().to_string(), step.type_.to_string()];
+    let data = |key: &str| step.data.get(key).and_then(|v| v.as_str());
+    let detail = match step.type_ {
+        ActionType::Webhook => data("url").map(|url| match data("method") {
+            Some(method) => format!("{} {}", method, url),
+            None => url.to_string(),
+        }),
+        ActionType::NimbusContainer => data("image").map(str::to_string),
+        ActionType::LlmWorkflow => step.target.clone(),
+        ActionType::Event => data("topic").map(str::to_string),
+        ActionType::Script => None,
+    };
+    lines.extend(detail);
+    lines
+}
+
+fn dot_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('|', "#124;")
+        .replace('\n', " ")
+}
+
+fn mermaid_identifier(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsl::{Pipeline, PipelineBuilder};
+
+    fn pipeline() -> Pipeline {
+        PipelineBuilder::new("deploy")
+            .webhook("check", "https://example.com/health")
+            .then()
+            .container("build", "rust:1.80")
+            .depends_on("check")
+            .when("check.status == 200")
+            .then()
+            .output("build")
+            .build()
+    }
+
+    #[test]
+    fn renders_as_dot() {
+        assert_eq!(
+            pipeline().to_dot(),
+            r##"digraph "deploy" {
+  rankdir=LR;
+  node [shape=box, style=rounded];
+  "check" [label="check\nwebhook\nGET https://example.com/health"];
+  "build" [label="build\nnimbus_container\nrust:1.80", style="rounded,filled,bold", fillcolor="#fff2cc"];
+  "check" -> "build" [style=dashed, label="check.status == 200"];
+}
+"##
+        );
+    }
+
+    #[test]
+    fn renders_as_mermaid() {
+        assert_eq!(
+            pipeline().to_mermaid(),
+            r#"flowchart LR
+    s0["check<br/>webhook<br/>GET https://example.com/health"]
+    s1["build<br/>nimbus_container<br/>rust:1.80"]
+    s0 -.->|"check.status == 200"| s1
+    classDef output fill:#fff2cc,stroke:#d6b656,stroke-width:2px
+    class s1 output
+"#
+        );
+    }
+}
@@ -1,17 +1,46 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::container::{ContainerSpec, PullPolicy, RegistryCredentials, VolumeMount};
+use crate::llm::{LlmSpec, Provider, Route, Tool, ToolRegistry, Worker};
+use crate::trigger::Trigger;
+use crate::validation::ValidationErrors;
+use crate::webhook::{BodyFormat, HttpMethod, WebhookAuth, WebhookSpec};
+
 // --- ActionType and Step ---
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ActionType {
     Webhook,
     LlmWorkflow,
     Event,
+    #[serde(alias = "container")]
     NimbusContainer,
     Script,
 }
 
-#[derive(Debug, Clone)]
+impl ActionType {
+    /// The name used for this action type in pipeline definition files.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Webhook => "webhook",
+            ActionType::LlmWorkflow => "llm_workflow",
+            ActionType::Event => "event",
+            ActionType::NimbusContainer => "nimbus_container",
+            ActionType::Script => "script",
+        }
+    }
+}
+
+impl std::fmt::Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "crate::format::StepRepr", into = "crate::format::StepRepr")]
 pub struct Step {
     pub id: Option<String>,
     pub name: String,
@@ -19,19 +48,56 @@ pub struct Step {
     pub type_: ActionType,
     pub data: Value,
     pub target: Option<String>,
-    pub depends_on: Option<String>,
+    pub depends_on: Vec<String>,
     pub expression: Option<String>,
 }
 
+impl Step {
+    fn new(id: &str, type_: ActionType, data: Value) -> Self {
+        Step {
+            id: Some(id.to_string()),
+            name: id.to_string(),
+            key: id.to_string(),
+            type_,
+            data,
+            target: None,
+            depends_on: Vec::new(),
+            expression: None,
+        }
+    }
+
+    /// The id other steps and outputs use to reference this step: `id` when
+    /// set, otherwise `name`.
+    pub fn effective_id(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Returns `data` as a JSON object, replacing any non-object value.
+    fn data_mut(&mut self) -> &mut serde_json::Map<String, Value> {
+        if !self.data.is_object() {
+            self.data = Value::Object(Default::default());
+        }
+        self.data
+            .as_object_mut()
+            .expect("data was just made an object")
+    }
+
+    fn set_data(&mut self, key: &str, value: Value) {
+        self.data_mut().insert(key.to_string(), value);
+    }
+}
+
 // --- PipelineBuilder ---
 
-#[derive(Default)]
+/// Builds a [`Pipeline`]. Setting a [`trigger`](Self::trigger) is optional:
+/// the same pipeline can be run ad hoc with `run_pipeline` or, once it has a
+/// trigger, registered with `create_pipeline`.
+#[derive(Default, Debug, Clone)]
 pub struct PipelineBuilder {
     name: String,
-    trigger: Option<String>,
+    trigger: Option<Trigger>,
     steps: Vec<Step>,
     outputs: Vec<String>,
-    current_step: Option<Step>,
 }
 
 impl PipelineBuilder {
@@ -42,6 +108,11 @@ impl PipelineBuilder {
         }
     }
 
+    pub fn trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
     pub fn webhook(self, id: &str, url: &str) -> WebhookStepBuilder {
         WebhookStepBuilder::new(self, id, url)
     }
@@ -58,13 +129,37 @@ impl PipelineBuilder {
         LlmWorkflowStepBuilder::new(self, id)
     }
 
+    /// Adds a `routing` LLM step.
+    pub fn routing(self, id: &str) -> RoutingStepBuilder {
+        RoutingStepBuilder::new(self, id)
+    }
+
+    /// Adds an `orchestrator` LLM step.
+    pub fn orchestrator(self, id: &str) -> OrchestratorStepBuilder {
+        OrchestratorStepBuilder::new(self, id)
+    }
+
+    /// Adds a `prompt_chain` LLM step.
+    pub fn prompt_chain(self, id: &str) -> PromptChainStepBuilder {
+        PromptChainStepBuilder::new(self, id)
+    }
+
+    /// Adds an `evaluator_optimizer` LLM step.
+    pub fn evaluator_optimizer(self, id: &str) -> EvaluatorOptimizerStepBuilder {
+        EvaluatorOptimizerStepBuilder::new(self, id)
+    }
+
+    /// Adds a step that publishes a message to `topic` on the Helios event bus.
+    pub fn event(self, id: &str, topic: &str) -> EventStepBuilder {
+        EventStepBuilder::new(self, id, topic)
+    }
+
     pub fn output(mut self, id: &str) -> Self {
         self.outputs.push(id.to_string());
         self
     }
 
-    pub fn build(mut self) -> Pipeline {
-        self.push_current();
+    pub fn build(self) -> Pipeline {
         Pipeline {
             name: self.name,
             trigger: self.trigger,
@@ -73,25 +168,47 @@ impl PipelineBuilder {
         }
     }
 
-    fn push_current(&mut self) {
-        if let Some(step) = self.current_step.take() {
-            self.steps.push(step);
-        }
+    /// Builds the pipeline and runs [`Pipeline::validate`] on it.
+    pub fn try_build(self) -> Result<Pipeline, ValidationErrors> {
+        let pipeline = self.build();
+        pipeline.validate()?;
+        Ok(pipeline)
+    }
+
+    fn push_step(&mut self, step: Step) {
+        self.steps.push(step);
     }
 }
 
+#[deprecated(note = "use `PipelineBuilder`; `trigger` is an optional attribute on it")]
+pub type CreatePipelineBuilder = PipelineBuilder;
+
 // --- Pipeline struct for build() output ---
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Pipeline {
     pub name: String,
-    pub trigger: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<Trigger>,
     pub steps: Vec<Step>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub outputs: Vec<String>,
 }
 
 // --- Conversion to GraphQL input types ---
 
+/// Wire form of a dependency list: `dependsOn` carries the first id, as
+/// servers that know a single dependency expect, and `dependsOnAll` carries
+/// the full list when there is more than one. Fan-in therefore needs a
+/// server that accepts `dependsOnAll`; older servers reject the unknown field
+/// instead of running the step after its first dependency only.
+fn encode_depends_on(ids: Vec<String>) -> (Option<String>, Option<Vec<String>>) {
+    let first = ids.first().cloned();
+    let all = (ids.len() > 1).then_some(ids);
+    (first, all)
+}
+
 use crate::create_pipeline;
 use crate::run_pipeline;
 
@@ -123,13 +240,15 @@ impl From<ActionType> for run_pipeline::run_pipeline::ActionType {
 
 impl From<Step> for create_pipeline::create_pipeline::StepInput {
     fn from(s: Step) -> Self {
+        let (depends_on, depends_on_all) = encode_depends_on(s.depends_on);
         create_pipeline::create_pipeline::StepInput {
             name: s.name,
             key: s.key,
             type_: s.type_.into(),
             data: s.data,
             target: s.target,
-            depends_on: s.depends_on,
+            depends_on,
+            depends_on_all,
             expression: s.expression,
         }
     }
@@ -137,6 +256,7 @@ impl From<Step> for create_pipeline::create_pipeline::StepInput {
 
 impl From<Step> for run_pipeline::run_pipeline::StepInput {
     fn from(s: Step) -> Self {
+        let (depends_on, depends_on_all) = encode_depends_on(s.depends_on);
         run_pipeline::run_pipeline::StepInput {
             id: s.id.unwrap_or_else(|| s.name.clone()),
             name: s.name,
@@ -144,19 +264,23 @@ impl From<Step> for run_pipeline::run_pipeline::StepInput {
             type_: s.type_.into(),
             data: s.data,
             target: s.target,
-            depends_on: s.depends_on,
+            depends_on,
+            depends_on_all,
             expression: s.expression,
         }
     }
 }
 
-impl From<Pipeline> for create_pipeline::create_pipeline::CreatePipelineInput {
-    fn from(p: Pipeline) -> Self {
-        create_pipeline::create_pipeline::CreatePipelineInput {
+impl TryFrom<Pipeline> for create_pipeline::create_pipeline::CreatePipelineInput {
+    type Error = crate::HeliosClientError;
+
+    fn try_from(p: Pipeline) -> Result<Self, Self::Error> {
+        let trigger = p.trigger.ok_or(crate::HeliosClientError::MissingTrigger)?;
+        Ok(create_pipeline::create_pipeline::CreatePipelineInput {
             name: p.name,
-            trigger: p.trigger.unwrap_or_else(|| "manual".to_string()),
+            trigger: trigger.to_string(),
             steps: p.steps.into_iter().map(Into::into).collect(),
-        }
+        })
     }
 }
 
@@ -170,132 +294,217 @@ impl From<Pipeline> for run_pipeline::run_pipeline::RunPipelineInput {
     }
 }
 
+// --- Step builders ---
+
+/// Implements the methods every step builder shares. Options that apply to
+/// all step kinds belong here so each builder picks them up.
+macro_rules! impl_step_builder {
+    ($builder:ident) => {
+        impl $builder {
+            fn step_mut(&mut self) -> &mut Step {
+                self.pipeline
+                    .steps
+                    .last_mut()
+                    .expect("a step builder always has a current step")
+            }
+
+            pub fn depends_on(mut self, id: &str) -> Self {
+                self.step_mut().depends_on.push(id.to_string());
+                self
+            }
+
+            pub fn depends_on_all(mut self, ids: &[&str]) -> Self {
+                self.step_mut()
+                    .depends_on
+                    .extend(ids.iter().map(|id| id.to_string()));
+                self
+            }
+
+            /// Runs the step only when `expression` holds. Accepts the raw
+            /// string form or an [`Expr`](crate::expression::Expr).
+            pub fn when(mut self, expression: impl Into<String>) -> Self {
+                self.step_mut().expression = Some(expression.into());
+                self
+            }
+
+            pub fn then(self) -> PipelineBuilder {
+                self.pipeline
+            }
+        }
+    };
+}
+
 // --- WebhookStepBuilder ---
 
 pub struct WebhookStepBuilder {
     pipeline: PipelineBuilder,
-    step_id: String,
+    /// Whether the `content-type` header was set by [`Self::form`] or
+    /// [`Self::text`] rather than by the caller.
+    auto_content_type: bool,
 }
 
+impl_step_builder!(WebhookStepBuilder);
+
 impl WebhookStepBuilder {
     pub fn new(mut pipeline: PipelineBuilder, id: &str, url: &str) -> Self {
-        pipeline.push_current();
-        let step = Step {
-            id: Some(id.to_string()),
-            name: id.to_string(),
-            key: id.to_string(),
-            type_: ActionType::Webhook,
-            data: serde_json::json!({ "url": url, "method": "GET" }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.current_step = Some(step);
+        pipeline.push_step(Step::new(
+            id,
+            ActionType::Webhook,
+            WebhookSpec::new(url).to_data(),
+        ));
         Self {
             pipeline,
-            step_id: id.to_string(),
+            auto_content_type: false,
         }
     }
 
-    pub fn method(mut self, method: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("method".to_string(), serde_json::json!(method));
-            step.data = serde_json::json!(data);
-        }
-        self
+    pub fn method(self, method: HttpMethod) -> Self {
+        self.update_spec(|spec| spec.method = method)
     }
 
+    /// Sets a header. A `content-type` set here is kept when the body
+    /// changes.
     pub fn header(mut self, key: &str, value: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            let headers = data
-                .entry("headers")
-                .or_insert_with(|| serde_json::json!({}));
-            if let Some(headers_map) = headers.as_object_mut() {
-                headers_map.insert(key.to_string(), serde_json::json!(value));
-            }
-            step.data = serde_json::json!(data);
+        let content_type = key.eq_ignore_ascii_case("content-type");
+        if content_type {
+            self.auto_content_type = false;
         }
-        self
+        self.update_spec(|spec| {
+            if content_type {
+                spec.headers
+                    .retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+            }
+            spec.headers.insert(key.to_string(), value.to_string());
+        })
     }
 
-    pub fn body<T: serde::Serialize>(mut self, body: &T) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert(
-                "body".to_string(),
-                serde_json::to_value(body).unwrap_or(serde_json::json!(null)),
-            );
-            step.data = serde_json::json!(data);
-        }
-        self
+    /// Appends a query parameter to the URL, percent-encoding the key and
+    /// value.
+    pub fn query(self, key: &str, value: &str) -> Self {
+        self.update_spec(|spec| spec.append_query(key, value))
     }
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.depends_on = Some(id.to_string());
-        }
+    /// Sends `body` as JSON, dropping a `content-type` set by
+    /// [`Self::form`] or [`Self::text`].
+    ///
+    /// # Panics
+    ///
+    /// If `body` cannot be represented as JSON, e.g. a map with non-string
+    /// keys.
+    pub fn body<T: serde::Serialize>(mut self, body: &T) -> Self {
+        let body = serde_json::to_value(body)
+            .unwrap_or_else(|e| panic!("webhook body does not serialize to JSON: {}", e));
+        let auto_content_type = std::mem::take(&mut self.auto_content_type);
+        self.update_spec(|spec| {
+            spec.body = Some(body);
+            spec.body_format = None;
+            if auto_content_type {
+                spec.headers
+                    .retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+            }
+        })
+    }
+
+    /// Sends `fields` as an `application/x-www-form-urlencoded` body, with
+    /// that content type unless one was set with [`Self::header`].
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        self.raw_body(
+            crate::webhook::encode_form(fields),
+            BodyFormat::Form,
+            "application/x-www-form-urlencoded",
+        )
+    }
+
+    /// Sends `text` as-is, as `text/plain` unless a `content-type` header
+    /// was set with [`Self::header`].
+    pub fn text(self, text: &str) -> Self {
+        self.raw_body(
+            text.to_string(),
+            BodyFormat::Text,
+            "text/plain; charset=utf-8",
+        )
+    }
+
+    /// Authenticates with a bearer token read from secret `token_secret`.
+    pub fn bearer_auth(self, token_secret: &str) -> Self {
+        self.update_spec(|spec| {
+            spec.auth = Some(WebhookAuth::Bearer {
+                token_secret: token_secret.to_string(),
+            })
+        })
+    }
+
+    /// Authenticates as `username`, with the password read from secret
+    /// `password_secret`.
+    pub fn basic_auth(self, username: &str, password_secret: &str) -> Self {
+        self.update_spec(|spec| {
+            spec.auth = Some(WebhookAuth::Basic {
+                username: username.to_string(),
+                password_secret: password_secret.to_string(),
+            })
+        })
+    }
+
+    pub fn timeout(self, seconds: u64) -> Self {
+        self.update_spec(|spec| spec.timeout = Some(seconds))
+    }
+
+    /// Adds a status that counts as success. Once any is set, other
+    /// statuses, including unlisted 2xx ones, fail the step.
+    pub fn expect_status(self, status: u16) -> Self {
+        self.update_spec(|spec| spec.expected_status.push(status))
+    }
+
+    /// Sets a string body in `format`, replacing a content type set by an
+    /// earlier body but not one set with [`Self::header`].
+    fn raw_body(mut self, body: String, format: BodyFormat, content_type: &str) -> Self {
+        let mut auto_content_type = self.auto_content_type;
+        self = self.update_spec(|spec| {
+            spec.body = Some(Value::String(body));
+            spec.body_format = Some(format);
+            if auto_content_type || spec.header("content-type").is_none() {
+                spec.headers
+                    .retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+                spec.headers
+                    .insert("content-type".to_string(), content_type.to_string());
+                auto_content_type = true;
+            }
+        });
+        self.auto_content_type = auto_content_type;
         self
     }
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.expression = Some(expression.to_string());
-        }
+    /// Applies `update` to the step's spec. Data that no longer reads as a
+    /// [`WebhookSpec`] is replaced by a fresh spec for the same URL.
+    fn update_spec(mut self, update: impl FnOnce(&mut WebhookSpec)) -> Self {
+        let step = self.step_mut();
+        let mut spec = WebhookSpec::from_data(&step.data).unwrap_or_else(|e| {
+            tracing::warn!(step = %step.name, error = %e, "resetting invalid webhook step data");
+            let url = step.data.get("url").and_then(Value::as_str);
+            WebhookSpec::new(url.unwrap_or_default())
+        });
+        update(&mut spec);
+        step.data = spec.to_data();
         self
     }
-
-    pub fn then(mut self) -> PipelineBuilder {
-        self.pipeline.push_current();
-        self.pipeline
-    }
 }
 
 // --- ScriptStepBuilder ---
 
 pub struct ScriptStepBuilder {
     pipeline: PipelineBuilder,
-    step_id: String,
 }
 
+impl_step_builder!(ScriptStepBuilder);
+
 impl ScriptStepBuilder {
     pub fn new(mut pipeline: PipelineBuilder, id: &str, script: &str) -> Self {
-        pipeline.push_current();
-        let step = Step {
-            id: Some(id.to_string()),
-            name: id.to_string(),
-            key: id.to_string(),
-            type_: ActionType::Script,
-            data: serde_json::json!({ "script": script }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.current_step = Some(step);
-        Self {
-            pipeline,
-            step_id: id.to_string(),
-        }
-    }
-
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.depends_on = Some(id.to_string());
-        }
-        self
-    }
-
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.expression = Some(expression.to_string());
-        }
-        self
-    }
-
-    pub fn then(mut self) -> PipelineBuilder {
-        self.pipeline.push_current();
-        self.pipeline
+        pipeline.push_step(Step::new(
+            id,
+            ActionType::Script,
+            serde_json::json!({ "script": script }),
+        ));
+        Self { pipeline }
     }
 }
 
@@ -303,103 +512,127 @@ impl ScriptStepBuilder {
 
 pub struct ContainerStepBuilder {
     pipeline: PipelineBuilder,
-    step_id: String,
 }
 
+impl_step_builder!(ContainerStepBuilder);
+
 impl ContainerStepBuilder {
     pub fn new(mut pipeline: PipelineBuilder, id: &str, image: &str) -> Self {
-        pipeline.push_current();
-        let step = Step {
-            id: Some(id.to_string()),
-            name: id.to_string(),
-            key: id.to_string(),
-            type_: ActionType::NimbusContainer,
-            data: serde_json::json!({ "image": image, "step_id": id }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.current_step = Some(step);
-        Self {
-            pipeline,
-            step_id: id.to_string(),
-        }
+        pipeline.push_step(Step::new(
+            id,
+            ActionType::NimbusContainer,
+            ContainerSpec::new(image, id).to_data(),
+        ));
+        Self { pipeline }
+    }
+
+    /// Replaces the whole container configuration. The spec's `step_id` is
+    /// set to this step's id.
+    pub fn spec(mut self, mut spec: ContainerSpec) -> Self {
+        let step = self.step_mut();
+        spec.step_id = step.effective_id().to_string();
+        step.data = spec.to_data();
+        self
     }
 
-    pub fn with_args(mut self, args: &[&str]) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("command".to_string(), serde_json::json!(args));
-            step.data = serde_json::json!(data);
-        }
-        self
+    pub fn with_args(self, args: &[&str]) -> Self {
+        self.update_spec(|spec| spec.command = Some(to_strings(args)))
     }
 
-    pub fn with_volume(mut self, host_path: &str, container_path: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            let volumes = data
-                .entry("volumes")
-                .or_insert_with(|| serde_json::json!([]));
-            if let Some(vols) = volumes.as_array_mut() {
-                vols.push(serde_json::json!({
-                    "host_path": host_path,
-                    "container_path": container_path,
-                    "read_only": false
-                }));
-            }
-            step.data = serde_json::json!(data);
-        }
-        self
+    /// Overrides the image entrypoint.
+    pub fn entrypoint(self, entrypoint: &[&str]) -> Self {
+        self.update_spec(|spec| spec.entrypoint = Some(to_strings(entrypoint)))
     }
 
-    pub fn platform(mut self, platform: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("platform".to_string(), serde_json::json!(platform));
-            step.data = serde_json::json!(data);
-        }
-        self
+    pub fn env(self, key: &str, value: &str) -> Self {
+        self.update_spec(|spec| {
+            spec.env.insert(key.to_string(), value.to_string());
+        })
     }
 
-    pub fn timeout(mut self, seconds: u64) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("timeout".to_string(), serde_json::json!(seconds));
-            step.data = serde_json::json!(data);
-        }
-        self
+    #[deprecated(note = "use `bind_mount`, `named_volume`, `scratch_volume` or `mount`")]
+    pub fn with_volume(self, host_path: &str, container_path: &str) -> Self {
+        self.bind_mount(host_path, container_path)
     }
 
-    pub fn working_dir(mut self, dir: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("working_dir".to_string(), serde_json::json!(dir));
-            step.data = serde_json::json!(data);
-        }
-        self
+    /// Adds a volume mount; see [`VolumeMount`] for the sources and the
+    /// read-only option.
+    pub fn mount(self, mount: VolumeMount) -> Self {
+        self.update_spec(|spec| spec.volumes.push(mount))
     }
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.depends_on = Some(id.to_string());
-        }
-        self
+    /// Bind-mounts a host directory read-write.
+    pub fn bind_mount(self, host_path: &str, container_path: &str) -> Self {
+        self.mount(VolumeMount::host(host_path, container_path))
     }
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.expression = Some(expression.to_string());
-        }
-        self
+    /// Mounts a named (persistent) volume read-write.
+    pub fn named_volume(self, name: &str, container_path: &str) -> Self {
+        self.mount(VolumeMount::named(name, container_path))
+    }
+
+    /// Mounts an empty volume that is discarded after the step.
+    pub fn scratch_volume(self, container_path: &str) -> Self {
+        self.mount(VolumeMount::scratch(container_path))
+    }
+
+    pub fn platform(self, platform: &str) -> Self {
+        self.update_spec(|spec| spec.platform = Some(platform.to_string()))
+    }
+
+    pub fn timeout(self, seconds: u64) -> Self {
+        self.update_spec(|spec| spec.timeout = Some(seconds))
+    }
+
+    pub fn working_dir(self, dir: &str) -> Self {
+        self.update_spec(|spec| spec.working_dir = Some(dir.to_string()))
+    }
+
+    /// Limits the container to `cpus` cores; fractions are allowed.
+    pub fn cpu_limit(self, cpus: f64) -> Self {
+        self.update_spec(|spec| {
+            spec.resources.get_or_insert_with(Default::default).cpus = Some(cpus)
+        })
+    }
+
+    pub fn memory_limit(self, bytes: u64) -> Self {
+        self.update_spec(|spec| {
+            spec.resources
+                .get_or_insert_with(Default::default)
+                .memory_bytes = Some(bytes)
+        })
     }
 
-    pub fn then(mut self) -> PipelineBuilder {
-        self.pipeline.push_current();
-        self.pipeline
+    pub fn pull_policy(self, policy: PullPolicy) -> Self {
+        self.update_spec(|spec| spec.pull_policy = Some(policy))
+    }
+
+    pub fn registry_credentials(self, credentials: RegistryCredentials) -> Self {
+        self.update_spec(|spec| spec.registry_auth = Some(credentials))
+    }
+
+    /// Applies `update` to the step's spec. Data that no longer reads as a
+    /// [`ContainerSpec`] is replaced by a fresh spec for the same image.
+    fn update_spec(mut self, update: impl FnOnce(&mut ContainerSpec)) -> Self {
+        let step = self.step_mut();
+        let mut spec = ContainerSpec::from_data(&step.data).unwrap_or_else(|e| {
+            tracing::warn!(step = %step.name, error = %e, "resetting invalid container step data");
+            let image = step.data.get("image").and_then(Value::as_str);
+            ContainerSpec::new(image.unwrap_or_default(), step.effective_id())
+        });
+        update(&mut spec);
+        step.data = spec.to_data();
+        self
     }
 }
 
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+// --- LlmWorkflowStepBuilder ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LLMWorkflowTypes {
     Generic,
     PromptChain,
@@ -422,400 +655,606 @@ impl std::fmt::Display for LLMWorkflowTypes {
     }
 }
 
-pub struct LlmWorkflowStepBuilder {
-    pipeline: PipelineBuilder,
-    step_id: String,
-}
-impl LlmWorkflowStepBuilder {
-    pub fn new(mut pipeline: PipelineBuilder, id: &str) -> Self {
-        pipeline.push_current();
-        let step = Step {
-            id: Some(id.to_string()),
-            name: id.to_string(),
-            key: id.to_string(),
-            type_: ActionType::LlmWorkflow,
-            data: serde_json::json!({}),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.current_step = Some(step);
-        Self {
-            pipeline,
-            step_id: id.to_string(),
-        }
-    }
+impl LLMWorkflowTypes {
+    const ALL: [LLMWorkflowTypes; 6] = [
+        LLMWorkflowTypes::Generic,
+        LLMWorkflowTypes::PromptChain,
+        LLMWorkflowTypes::Routing,
+        LLMWorkflowTypes::Orchestrator,
+        LLMWorkflowTypes::Agent,
+        LLMWorkflowTypes::EvaluationOptimization,
+    ];
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.depends_on = Some(id.to_string());
-        }
-        self
+    /// The workflow type a step `target` names, if any.
+    pub fn from_target(target: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|w| w.to_string() == target)
     }
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.expression = Some(expression.to_string());
+    /// The builder for workflow types that need more than a prompt, named
+    /// by its [`PipelineBuilder`] method.
+    fn dedicated_builder(&self) -> Option<&'static str> {
+        match self {
+            LLMWorkflowTypes::Generic | LLMWorkflowTypes::Agent => None,
+            LLMWorkflowTypes::PromptChain => Some("prompt_chain"),
+            LLMWorkflowTypes::Routing => Some("routing"),
+            LLMWorkflowTypes::Orchestrator => Some("orchestrator"),
+            LLMWorkflowTypes::EvaluationOptimization => Some("evaluator_optimizer"),
         }
-        self
     }
+}
 
-    pub fn then(mut self) -> PipelineBuilder {
-        self.pipeline.push_current();
-        self.pipeline
-    }
+/// Panics unless `value` is finite: NaN and infinities serialize to `null`,
+/// so the setting would silently disappear from the step.
+fn assert_finite(setting: &str, value: f64) {
+    assert!(
+        value.is_finite(),
+        "{} must be a finite number, got {}",
+        setting,
+        value
+    );
+}
 
-    pub fn prompt(mut self, prompt: &str) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("prompt".to_string(), serde_json::json!(prompt));
-            step.data = serde_json::json!(data);
-        }
-        self
-    }
+/// Generation settings shared by every LLM step builder, all written
+/// through [`LlmSpec`].
+macro_rules! impl_llm_step_builder {
+    ($builder:ident, $workflow:expr) => {
+        impl_step_builder!($builder);
+
+        impl $builder {
+            pub fn new(mut pipeline: PipelineBuilder, id: &str) -> Self {
+                let mut step = Step::new(id, ActionType::LlmWorkflow, serde_json::json!({}));
+                let workflow: Option<LLMWorkflowTypes> = $workflow;
+                step.target = workflow.map(|w| w.to_string());
+                pipeline.push_step(step);
+                Self { pipeline }
+            }
 
-    // stores the workfow type on step.target as a string
-    pub fn workflow_type(mut self, workflow_type: &LLMWorkflowTypes) -> Self {
-        if let Some(ref mut step) = self.pipeline.current_step {
-            step.target = Some(workflow_type.to_string());
-        }
-        self
-    }
-}
+            pub fn prompt(self, prompt: &str) -> Self {
+                self.update_spec(|spec| spec.prompt = Some(prompt.to_string()))
+            }
 
-#[derive(Default, Debug, Clone)]
-pub struct CreatePipelineBuilder {
-    pub name: String,
-    pub trigger: Option<String>,
-    pub steps: Vec<Step>,
-    pub outputs: Vec<String>,
-    pub current_step: Option<Step>,
-}
+            pub fn system_prompt(self, system_prompt: &str) -> Self {
+                self.update_spec(|spec| spec.system_prompt = Some(system_prompt.to_string()))
+            }
 
-impl CreatePipelineBuilder {
-    pub fn new(name: &str) -> Self {
-        CreatePipelineBuilder {
-            name: name.to_string(),
-            ..Default::default()
-        }
-    }
+            pub fn provider(self, provider: Provider) -> Self {
+                self.update_spec(|spec| spec.provider = Some(provider))
+            }
 
-    pub fn trigger(mut self, trigger: &str) -> Self {
-        self.trigger = Some(trigger.to_string());
-        self
-    }
+            pub fn model(self, model: &str) -> Self {
+                self.update_spec(|spec| spec.model = Some(model.to_string()))
+            }
 
-    pub fn webhook(self, name: &str, url: &str) -> CreateWebhookStepBuilder {
-        CreateWebhookStepBuilder::new(self, name, url)
-    }
+            /// Sampling temperature; validation requires it to be within
+            /// [`TEMPERATURE_RANGE`](crate::llm::TEMPERATURE_RANGE).
+            ///
+            /// # Panics
+            ///
+            /// If `temperature` is NaN or infinite, which JSON cannot carry.
+            pub fn temperature(self, temperature: f64) -> Self {
+                assert_finite("temperature", temperature);
+                self.update_spec(|spec| spec.temperature = Some(temperature))
+            }
 
-    pub fn script(self, name: &str, cmd: &str) -> CreateScriptStepBuilder {
-        CreateScriptStepBuilder::new(self, name, cmd)
-    }
+            /// Nucleus sampling; validation requires `0 < top_p <= 1`.
+            ///
+            /// # Panics
+            ///
+            /// If `top_p` is NaN or infinite, which JSON cannot carry.
+            pub fn top_p(self, top_p: f64) -> Self {
+                assert_finite("top_p", top_p);
+                self.update_spec(|spec| spec.top_p = Some(top_p))
+            }
 
-    pub fn container(self, name: &str, image: &str) -> CreateContainerStepBuilder {
-        CreateContainerStepBuilder::new(self, name, image)
-    }
+            pub fn max_tokens(self, max_tokens: u32) -> Self {
+                self.update_spec(|spec| spec.max_tokens = Some(max_tokens))
+            }
 
-    pub fn llm_workflow(self, name: &str) -> CreateLlmWorkflowStepBuilder {
-        CreateLlmWorkflowStepBuilder::new(self, name)
-    }
+            /// Adds a sequence that ends generation.
+            pub fn stop_sequence(self, stop: &str) -> Self {
+                self.update_spec(|spec| spec.stop_sequences.push(stop.to_string()))
+            }
 
-    pub fn output(mut self, id: &str) -> Self {
-        self.outputs.push(id.to_string());
-        self
-    }
+            /// Requires the step result to match the JSON Schema of `T`. The
+            /// schema is sent with the step and checked when the run completes.
+            #[cfg(feature = "schema")]
+            pub fn output_schema<T: schemars::JsonSchema>(self) -> Self {
+                let schema = serde_json::to_value(schemars::schema_for!(T))
+                    .expect("JSON Schema serializes to JSON");
+                self.output_schema_value(schema)
+            }
 
-    pub fn build(mut self) -> Pipeline {
-        self.push_current();
-        Pipeline {
-            name: self.name,
-            trigger: self.trigger,
-            steps: self.steps,
-            outputs: self.outputs,
-        }
-    }
+            /// Requires the step result to match `schema`, a JSON Schema
+            /// document. The schema is sent with the step and checked when
+            /// the run completes; with the `schema` feature,
+            /// `output_schema::<T>()` derives it instead.
+            pub fn output_schema_value(self, schema: Value) -> Self {
+                self.update_spec(|spec| spec.output_schema = Some(schema))
+            }
 
-    fn push_current(&mut self) {
-        if let Some(step) = self.current_step.take() {
-            self.steps.push(step);
+            /// Adds every step the prompt templates read (see
+            /// [`LlmSpec::templates`]) to `depends_on`, skipping ones already
+            /// listed. Call after setting the prompts; a malformed template
+            /// is left for validation to report.
+            pub fn infer_dependencies(mut self) -> Self {
+                let step = self.step_mut();
+                let templates: Vec<String> = LlmSpec::from_data(&step.data)
+                    .map(|spec| spec.templates().map(|(_, t)| t.to_string()).collect())
+                    .unwrap_or_default();
+                let refs: Vec<String> = templates
+                    .iter()
+                    .filter_map(|prompt| crate::template::step_refs(prompt).ok())
+                    .flatten()
+                    .map(str::to_string)
+                    .collect();
+                for reference in refs {
+                    if reference != step.effective_id() && !step.depends_on.contains(&reference) {
+                        step.depends_on.push(reference);
+                    }
+                }
+                self
+            }
+
+            /// Applies `update` to the step's spec. Data that no longer reads
+            /// as an [`LlmSpec`] is replaced by a fresh spec with the same
+            /// prompt.
+            fn update_spec(mut self, update: impl FnOnce(&mut LlmSpec)) -> Self {
+                let step = self.step_mut();
+                let mut spec = LlmSpec::from_data(&step.data).unwrap_or_else(|e| {
+                    tracing::warn!(step = %step.name, error = %e, "resetting invalid LLM step data");
+                    LlmSpec {
+                        prompt: step.data.get("prompt").and_then(Value::as_str).map(str::to_string),
+                        ..Default::default()
+                    }
+                });
+                update(&mut spec);
+                step.data = spec.to_data();
+                self
+            }
         }
-    }
+    };
 }
 
-pub struct CreateWebhookStepBuilder {
-    pipeline: CreatePipelineBuilder,
-    step_name: String,
+pub struct LlmWorkflowStepBuilder {
+    pipeline: PipelineBuilder,
 }
 
-impl CreateWebhookStepBuilder {
-    pub fn new(mut pipeline: CreatePipelineBuilder, name: &str, url: &str) -> Self {
-        let step = Step {
-            id: None,
-            name: name.to_string(),
-            key: name.to_string(),
-            type_: ActionType::Webhook,
-            data: serde_json::json!({ "url": url, "method": "GET" }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.steps.push(step);
-        Self {
-            pipeline,
-            step_name: name.to_string(),
-        }
-    }
-    pub fn method(mut self, method: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("method".to_string(), serde_json::json!(method));
-            step.data = serde_json::json!(data);
-        }
-        self
-    }
+impl_llm_step_builder!(LlmWorkflowStepBuilder, None);
 
-    pub fn header(mut self, key: &str, value: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            let headers = data
-                .entry("headers")
-                .or_insert_with(|| serde_json::json!({}));
-            if let Some(headers_map) = headers.as_object_mut() {
-                headers_map.insert(key.to_string(), serde_json::json!(value));
-            }
-            step.data = serde_json::json!(data);
-        }
-        self
-    }
-
-    pub fn body<T: serde::Serialize>(mut self, body: &T) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert(
-                "body".to_string(),
-                serde_json::to_value(body).unwrap_or(serde_json::json!(null)),
+impl LlmWorkflowStepBuilder {
+    /// Lets an agent workflow call `tool`. A tool with the same name
+    /// replaces the earlier one.
+    pub fn tool(self, tool: Tool) -> Self {
+        self.update_spec(|spec| {
+            spec.tools.retain(|t| t.name != tool.name);
+            spec.tools.push(tool);
+        })
+    }
+
+    /// Adds every tool in `registry`, as [`tool`](Self::tool) does.
+    pub fn tools(self, registry: &ToolRegistry) -> Self {
+        registry.iter().cloned().fold(self, Self::tool)
+    }
+
+    /// Sets the workflow type, stored as a string on the step's `target`.
+    ///
+    /// # Panics
+    ///
+    /// If `workflow_type` is a routing, orchestrator, prompt chain or
+    /// evaluator-optimizer workflow, which need the fields only their
+    /// dedicated builders set, such as [`PipelineBuilder::routing`].
+    pub fn workflow_type(mut self, workflow_type: &LLMWorkflowTypes) -> Self {
+        if let Some(builder) = workflow_type.dedicated_builder() {
+            panic!(
+                "`{}` workflows are built with `PipelineBuilder::{}`",
+                workflow_type, builder
             );
-            step.data = serde_json::json!(data);
         }
+        self.step_mut().target = Some(workflow_type.to_string());
         self
     }
+}
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.depends_on = Some(id.to_string());
-        }
-        self
-    }
+/// A `routing` workflow: the model picks one route by its condition and the
+/// pipeline continues at that route's target step.
+pub struct RoutingStepBuilder {
+    pipeline: PipelineBuilder,
+}
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.expression = Some(expression.to_string());
-        }
-        self
+impl_llm_step_builder!(RoutingStepBuilder, Some(LLMWorkflowTypes::Routing));
+
+impl RoutingStepBuilder {
+    /// Adds a route to `target` taken when the model judges `condition`
+    /// true. The target must depend on this step.
+    pub fn route(self, condition: &str, target: &str) -> Self {
+        self.update_spec(|spec| spec.routes.push(Route::new(condition, target)))
     }
 
-    pub fn then(self) -> CreatePipelineBuilder {
-        self.pipeline
+    /// The step taken when no route applies.
+    pub fn default_route(self, target: &str) -> Self {
+        self.update_spec(|spec| spec.default_route = Some(target.to_string()))
     }
 }
 
-pub struct CreateScriptStepBuilder {
-    pipeline: CreatePipelineBuilder,
-    step_name: String,
+/// An `orchestrator` workflow: the model splits the task between workers
+/// and combines their results.
+pub struct OrchestratorStepBuilder {
+    pipeline: PipelineBuilder,
 }
 
-impl CreateScriptStepBuilder {
-    pub fn new(mut pipeline: CreatePipelineBuilder, name: &str, script: &str) -> Self {
-        let step = Step {
-            id: None,
-            name: name.to_string(),
-            key: name.to_string(),
-            type_: ActionType::Script,
-            data: serde_json::json!({ "script": script }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.steps.push(step);
-        Self {
-            pipeline,
-            step_name: name.to_string(),
-        }
-    }
+impl_llm_step_builder!(
+    OrchestratorStepBuilder,
+    Some(LLMWorkflowTypes::Orchestrator)
+);
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.depends_on = Some(id.to_string());
-        }
-        self
+impl OrchestratorStepBuilder {
+    pub fn worker(self, worker: Worker) -> Self {
+        self.update_spec(|spec| spec.workers.push(worker))
     }
+}
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.expression = Some(expression.to_string());
-        }
-        self
-    }
+/// A `prompt_chain` workflow: each prompt runs in order on the result of the
+/// one before it, starting from [`prompt`](Self::prompt).
+pub struct PromptChainStepBuilder {
+    pipeline: PipelineBuilder,
+}
+
+impl_llm_step_builder!(PromptChainStepBuilder, Some(LLMWorkflowTypes::PromptChain));
 
-    pub fn then(self) -> CreatePipelineBuilder {
-        self.pipeline
+impl PromptChainStepBuilder {
+    /// Appends a prompt to the chain.
+    pub fn chain(self, prompt: &str) -> Self {
+        self.update_spec(|spec| spec.prompts.push(prompt.to_string()))
     }
 }
 
-pub struct CreateContainerStepBuilder {
-    pipeline: CreatePipelineBuilder,
-    step_name: String,
+/// An `evaluator_optimizer` workflow: one model drafts, another scores the
+/// draft against the criteria, and the draft is revised until it passes or
+/// the iteration limit is reached.
+pub struct EvaluatorOptimizerStepBuilder {
+    pipeline: PipelineBuilder,
 }
 
-impl CreateContainerStepBuilder {
-    pub fn new(mut pipeline: CreatePipelineBuilder, name: &str, image: &str) -> Self {
-        let step = Step {
-            id: None,
-            name: name.to_string(),
-            key: name.to_string(),
-            type_: ActionType::NimbusContainer,
-            data: serde_json::json!({ "image": image, "step_id": name }),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.steps.push(step);
-        Self {
-            pipeline,
-            step_name: name.to_string(),
-        }
-    }
+impl_llm_step_builder!(
+    EvaluatorOptimizerStepBuilder,
+    Some(LLMWorkflowTypes::EvaluationOptimization)
+);
 
-    pub fn with_args(mut self, args: &[&str]) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("command".to_string(), serde_json::json!(args));
-            step.data = serde_json::json!(data);
-        }
-        self
+impl EvaluatorOptimizerStepBuilder {
+    /// Adds a criterion the evaluator checks drafts against.
+    pub fn criterion(self, criterion: &str) -> Self {
+        self.update_spec(|spec| spec.criteria.push(criterion.to_string()))
     }
 
-    pub fn with_volume(mut self, host_path: &str, container_path: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            let volumes = data
-                .entry("volumes")
-                .or_insert_with(|| serde_json::json!([]));
-            if let Some(vols) = volumes.as_array_mut() {
-                vols.push(serde_json::json!({
-                    "host_path": host_path,
-                    "container_path": container_path,
-                    "read_only": false
-                }));
-            }
-            step.data = serde_json::json!(data);
-        }
-        self
+    /// Upper bound on draft revisions; required, and must be positive.
+    pub fn max_iterations(self, max_iterations: u32) -> Self {
+        self.update_spec(|spec| spec.max_iterations = Some(max_iterations))
     }
+}
 
-    pub fn platform(mut self, platform: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("platform".to_string(), serde_json::json!(platform));
-            step.data = serde_json::json!(data);
-        }
-        self
-    }
+// --- EventStepBuilder ---
 
-    pub fn timeout(mut self, seconds: u64) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("timeout".to_string(), serde_json::json!(seconds));
-            step.data = serde_json::json!(data);
-        }
+pub struct EventStepBuilder {
+    pipeline: PipelineBuilder,
+}
+
+impl_step_builder!(EventStepBuilder);
+
+impl EventStepBuilder {
+    pub fn new(mut pipeline: PipelineBuilder, id: &str, topic: &str) -> Self {
+        pipeline.push_step(Step::new(
+            id,
+            ActionType::Event,
+            serde_json::json!({ "topic": topic }),
+        ));
+        Self { pipeline }
+    }
+
+    /// Publishes `payload` as the message body.
+    ///
+    /// # Panics
+    ///
+    /// If `payload` cannot be represented as JSON, e.g. a map with non-string
+    /// keys.
+    pub fn payload<T: serde::Serialize>(mut self, payload: &T) -> Self {
+        let payload = serde_json::to_value(payload)
+            .unwrap_or_else(|e| panic!("event payload does not serialize to JSON: {}", e));
+        self.step_mut().set_data("payload", payload);
         self
     }
 
-    pub fn working_dir(mut self, dir: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("working_dir".to_string(), serde_json::json!(dir));
-            step.data = serde_json::json!(data);
+    /// Adds a message attribute (the event bus equivalent of a header).
+    pub fn attribute(mut self, key: &str, value: &str) -> Self {
+        let attributes = self
+            .step_mut()
+            .data_mut()
+            .entry("attributes")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(attributes_map) = attributes.as_object_mut() {
+            attributes_map.insert(key.to_string(), serde_json::json!(value));
         }
         self
     }
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.depends_on = Some(id.to_string());
-        }
+    /// Sets the message key, which the event bus uses for ordering and
+    /// partitioning. Not to be confused with the step's own `key`.
+    pub fn key(mut self, key: &str) -> Self {
+        self.step_mut().set_data("key", serde_json::json!(key));
         self
     }
+}
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.expression = Some(expression.to_string());
+// --- Deprecated create-only builder names ---
+
+#[deprecated(note = "use `WebhookStepBuilder`")]
+pub type CreateWebhookStepBuilder = WebhookStepBuilder;
+#[deprecated(note = "use `ScriptStepBuilder`")]
+pub type CreateScriptStepBuilder = ScriptStepBuilder;
+#[deprecated(note = "use `ContainerStepBuilder`")]
+pub type CreateContainerStepBuilder = ContainerStepBuilder;
+#[deprecated(note = "use `LlmWorkflowStepBuilder`")]
+pub type CreateLlmWorkflowStepBuilder = LlmWorkflowStepBuilder;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fan_in() -> Pipeline {
+        PipelineBuilder::new("fan-in")
+            .script("a", "echo a")
+            .then()
+            .script("b", "echo b")
+            .then()
+            .script("c", "echo c")
+            .then()
+            .script("join", "echo join")
+            .depends_on("a")
+            .depends_on_all(&["b", "c"])
+            .then()
+            .script("single", "echo single")
+            .depends_on("a")
+            .then()
+            .build()
+    }
+
+    #[test]
+    fn depends_on_accumulates_and_depends_on_all_extends() {
+        let pipeline = fan_in();
+        assert_eq!(pipeline.steps[3].depends_on, ["a", "b", "c"]);
+        assert_eq!(pipeline.steps[4].depends_on, ["a"]);
+    }
+
+    #[test]
+    fn single_dependency_encodes_as_before() {
+        let step: run_pipeline::run_pipeline::StepInput = fan_in().steps.remove(4).into();
+        let wire = serde_json::to_value(&step).unwrap();
+        assert_eq!(wire["dependsOn"], "a");
+        assert!(wire.get("dependsOnAll").is_none());
+    }
+
+    #[test]
+    fn fan_in_adds_depends_on_all_and_keeps_the_first_id() {
+        for step in [
+            serde_json::to_value(run_pipeline::run_pipeline::StepInput::from(
+                fan_in().steps.remove(3),
+            )),
+            serde_json::to_value(create_pipeline::create_pipeline::StepInput::from(
+                fan_in().steps.remove(3),
+            )),
+        ] {
+            let wire = step.unwrap();
+            assert_eq!(wire["dependsOn"], "a");
+            assert_eq!(wire["dependsOnAll"], json!(["a", "b", "c"]));
         }
-        self
     }
 
-    pub fn then(self) -> CreatePipelineBuilder {
-        self.pipeline
+    #[test]
+    fn steps_without_dependencies_send_null() {
+        let step: run_pipeline::run_pipeline::StepInput = fan_in().steps.remove(0).into();
+        let wire = serde_json::to_value(&step).unwrap();
+        assert!(wire["dependsOn"].is_null());
+        assert!(wire.get("dependsOnAll").is_none());
     }
-}
 
-pub struct CreateLlmWorkflowStepBuilder {
-    pipeline: CreatePipelineBuilder,
-    step_name: String,
-}
+    #[test]
+    #[should_panic(expected = "temperature must be a finite number, got NaN")]
+    fn nan_temperature_is_rejected() {
+        PipelineBuilder::new("p")
+            .llm_workflow("ask")
+            .temperature(f64::NAN);
+    }
 
-impl CreateLlmWorkflowStepBuilder {
-    pub fn new(mut pipeline: CreatePipelineBuilder, name: &str) -> Self {
-        let step = Step {
-            id: None,
-            name: name.to_string(),
-            key: name.to_string(),
-            type_: ActionType::LlmWorkflow,
-            data: serde_json::json!({}),
-            target: None,
-            depends_on: None,
-            expression: None,
-        };
-        pipeline.steps.push(step);
-        Self {
-            pipeline,
-            step_name: name.to_string(),
-        }
+    #[test]
+    #[should_panic(expected = "top_p must be a finite number, got inf")]
+    fn infinite_top_p_is_rejected() {
+        PipelineBuilder::new("p")
+            .routing("route")
+            .top_p(f64::INFINITY);
     }
 
-    pub fn depends_on(mut self, id: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.depends_on = Some(id.to_string());
-        }
-        self
+    fn webhook() -> WebhookStepBuilder {
+        PipelineBuilder::new("p").webhook("call", "https://example.com/hook")
     }
 
-    pub fn when(mut self, expression: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.expression = Some(expression.to_string());
-        }
-        self
+    fn headers(builder: WebhookStepBuilder) -> Value {
+        builder.then().build().steps[0].data["headers"].clone()
     }
 
-    pub fn then(self) -> CreatePipelineBuilder {
-        self.pipeline
+    #[test]
+    fn body_changes_replace_automatic_content_types() {
+        let form = webhook().text("hi").form(&[("a", "1")]);
+        assert_eq!(
+            headers(form),
+            json!({ "content-type": "application/x-www-form-urlencoded" })
+        );
+
+        let json_body = webhook().form(&[("a", "1")]).body(&json!({ "a": 1 }));
+        assert_eq!(headers(json_body), Value::Null);
     }
 
-    pub fn prompt(mut self, prompt: &str) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            let mut data = step.data.as_object().cloned().unwrap_or_default();
-            data.insert("prompt".to_string(), serde_json::json!(prompt));
-            step.data = serde_json::json!(data);
+    #[test]
+    fn explicit_content_types_survive_body_changes() {
+        let before = webhook()
+            .header("Content-Type", "application/vnd.api+json")
+            .text("hi")
+            .body(&json!({}));
+        assert_eq!(
+            headers(before),
+            json!({ "Content-Type": "application/vnd.api+json" })
+        );
+
+        let after = webhook()
+            .form(&[("a", "1")])
+            .header("Content-Type", "text/csv")
+            .text("a,b")
+            .body(&json!({}));
+        assert_eq!(headers(after), json!({ "Content-Type": "text/csv" }));
+    }
+
+    #[test]
+    #[should_panic(expected = "webhook body does not serialize to JSON")]
+    fn unserializable_bodies_panic_clearly() {
+        let body = std::collections::BTreeMap::from([(vec![1u8], 1)]);
+        webhook().body(&body);
+    }
+
+    #[test]
+    #[should_panic(expected = "event payload does not serialize to JSON")]
+    fn unserializable_payloads_panic_clearly() {
+        let payload = std::collections::BTreeMap::from([(vec![1u8], 1)]);
+        PipelineBuilder::new("p")
+            .event("publish", "orders")
+            .payload(&payload);
+    }
+
+    #[test]
+    fn invalid_webhook_data_is_reset_instead_of_panicking() {
+        let mut builder = webhook();
+        builder.step_mut().data = json!({ "url": "https://example.com/hook", "method": 5 });
+
+        let data = builder.timeout(3).then().build().steps[0].data.clone();
+
+        assert_eq!(
+            data,
+            json!({ "url": "https://example.com/hook", "method": "GET", "timeout": 3 })
+        );
+    }
+
+    #[test]
+    fn invalid_container_data_is_reset_instead_of_panicking() {
+        let mut builder = PipelineBuilder::new("p").container("build", "rust:latest");
+        builder.step_mut().data = json!({ "image": "rust:latest", "env": "not a map" });
+
+        let data = builder.timeout(3).then().build().steps[0].data.clone();
+
+        assert_eq!(
+            data,
+            json!({ "image": "rust:latest", "step_id": "build", "timeout": 3 })
+        );
+    }
+
+    #[test]
+    fn invalid_llm_data_is_reset_instead_of_panicking() {
+        let mut builder = PipelineBuilder::new("p").llm_workflow("answer");
+        builder.step_mut().data = json!({ "prompt": "Answer", "max_tokens": "many" });
+
+        let data = builder.model("small").then().build().steps[0].data.clone();
+
+        assert_eq!(data, json!({ "prompt": "Answer", "model": "small" }));
+    }
+
+    #[test]
+    fn event_steps_carry_topic_payload_attributes_and_key() {
+        let pipeline = PipelineBuilder::new("events")
+            .script("compute", "echo 42")
+            .then()
+            .event("publish", "orders.created")
+            .payload(&json!({ "order": 42 }))
+            .attribute("source", "helios")
+            .key("order-42")
+            .depends_on("compute")
+            .then()
+            .build();
+
+        let step = &pipeline.steps[1];
+        assert_eq!(step.type_, ActionType::Event);
+        assert_eq!(step.key, "publish");
+        assert_eq!(
+            step.data,
+            json!({
+                "topic": "orders.created",
+                "payload": { "order": 42 },
+                "attributes": { "source": "helios" },
+                "key": "order-42",
+            })
+        );
+        assert_eq!(step.depends_on, ["compute"]);
+    }
+
+    #[test]
+    fn infer_dependencies_adds_steps_read_by_prompts() {
+        let pipeline = PipelineBuilder::new("llm")
+            .script("weather", "echo sunny")
+            .then()
+            .script("traffic", "echo busy")
+            .then()
+            .llm_workflow("advice")
+            .prompt("{{nested_json 'weather'}} and {{nested_json 'traffic'}} for {{ city }}")
+            .depends_on("weather")
+            .infer_dependencies()
+            .then()
+            .build();
+
+        assert_eq!(pipeline.steps[2].depends_on, ["weather", "traffic"]);
+    }
+
+    #[test]
+    fn infer_dependencies_reads_chained_prompts() {
+        let pipeline = PipelineBuilder::new("llm")
+            .script("ticket", "cat ticket.json")
+            .then()
+            .prompt_chain("bug")
+            .prompt("Summarize")
+            .chain("Report {{nested_json 'ticket'}}")
+            .infer_dependencies()
+            .then()
+            .build();
+
+        assert_eq!(pipeline.steps[1].depends_on, ["ticket"]);
+    }
+
+    #[test]
+    fn infer_dependencies_reads_system_prompts_and_instructions() {
+        let pipeline = PipelineBuilder::new("support")
+            .script("policy", "cat policy.md")
+            .then()
+            .script("ticket", "cat ticket.json")
+            .then()
+            .orchestrator("plan")
+            .system_prompt("Policy: {{nested_json 'policy'}}")
+            .worker(Worker::new("writer", "Answer {{nested_json 'ticket'}}"))
+            .infer_dependencies()
+            .then()
+            .build();
+
+        assert_eq!(pipeline.steps[2].depends_on, ["policy", "ticket"]);
+    }
+
+    #[test]
+    fn workflow_types_parse_from_their_target() {
+        for workflow in LLMWorkflowTypes::ALL {
+            let target = workflow.to_string();
+            assert_eq!(LLMWorkflowTypes::from_target(&target), Some(workflow));
         }
-        self
+        assert_eq!(LLMWorkflowTypes::from_target("agent"), None);
     }
 
-    // stores the workfow type on step.target as a string
-    pub fn workflow_type(mut self, workflow_type: &LLMWorkflowTypes) -> Self {
-        if let Some(step) = self.pipeline.steps.last_mut() {
-            step.target = Some(workflow_type.to_string());
-        }
-        self
+    #[test]
+    #[should_panic(expected = "`routing` workflows are built with `PipelineBuilder::routing`")]
+    fn generic_builders_reject_dedicated_workflow_types() {
+        PipelineBuilder::new("p")
+            .llm_workflow("triage")
+            .workflow_type(&LLMWorkflowTypes::Routing);
     }
 }
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use graphql_client::PathFragment;
+use serde_json::Value;
+
+/// `extensions.code` values that indicate a problem with the request itself
+/// rather than with the server.
+const CLIENT_ERROR_CODES: &[&str] = &[
+    "BAD_USER_INPUT",
+    "BAD_REQUEST",
+    "GRAPHQL_PARSE_FAILED",
+    "GRAPHQL_VALIDATION_FAILED",
+    "UNAUTHENTICATED",
+    "FORBIDDEN",
+    "NOT_FOUND",
+];
+
+/// A single error from a GraphQL response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLError(graphql_client::Error);
+
+impl GraphQLError {
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    /// Path to the response field that produced the error, if any.
+    pub fn path(&self) -> Option<&[PathFragment]> {
+        self.0.path.as_deref()
+    }
+
+    /// The path joined with `.`, e.g. `runPipeline.outputs.0`.
+    pub fn path_string(&self) -> Option<String> {
+        self.path().map(|path| {
+            path.iter()
+                .map(|fragment| fragment.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+    }
+
+    pub fn extensions(&self) -> Option<&HashMap<String, Value>> {
+        self.0.extensions.as_ref()
+    }
+
+    /// The machine-readable `extensions.code`, if the server sent one.
+    pub fn code(&self) -> Option<&str> {
+        self.extensions()?.get("code")?.as_str()
+    }
+
+    /// Whether the server attributed the error to the request.
+    pub fn is_client_error(&self) -> bool {
+        self.code()
+            .is_some_and(|code| CLIENT_ERROR_CODES.contains(&code))
+    }
+
+    pub fn into_inner(self) -> graphql_client::Error {
+        self.0
+    }
+}
+
+impl From<graphql_client::Error> for GraphQLError {
+    fn from(error: graphql_client::Error) -> Self {
+        Self(error)
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())?;
+        if let Some(code) = self.code() {
+            write!(f, " [{}]", code)?;
+        }
+        if let Some(path) = self.path_string() {
+            write!(f, " at {}", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GraphQLError {}
+
+/// The errors returned in a GraphQL response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLErrors(Vec<GraphQLError>);
+
+impl GraphQLErrors {
+    pub fn iter(&self) -> std::slice::Iter<'_, GraphQLError> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// All `extensions.code` values, in response order.
+    pub fn codes(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(GraphQLError::code)
+    }
+
+    pub fn has_code(&self, code: &str) -> bool {
+        self.codes().any(|c| c == code)
+    }
+
+    /// Whether every error was attributed to the request.
+    pub fn is_client_error(&self) -> bool {
+        !self.0.is_empty() && self.0.iter().all(GraphQLError::is_client_error)
+    }
+}
+
+impl From<Vec<graphql_client::Error>> for GraphQLErrors {
+    fn from(errors: Vec<graphql_client::Error>) -> Self {
+        Self(errors.into_iter().map(GraphQLError).collect())
+    }
+}
+
+impl fmt::Display for GraphQLErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl IntoIterator for GraphQLErrors {
+    type Item = GraphQLError;
+    type IntoIter = std::vec::IntoIter<GraphQLError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a GraphQLErrors {
+    type Item = &'a GraphQLError;
+    type IntoIter = std::slice::Iter<'a, GraphQLError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
@@ -0,0 +1,839 @@
+//! `when` conditions.
+//!
+//! Helios evaluates a step's `expression` against the outputs of earlier
+//! steps. The syntax is:
+//!
+//! ```text
+//! expr     := or
+//! or       := and ("||" and)*
+//! and      := not ("&&" not)*
+//! not      := "!" not | compare
+//! compare  := operand (("==" | "!=" | "<" | "<=" | ">" | ">=") operand)?
+//! operand  := literal | path | "(" expr ")"
+//! path     := step_id ("." field | "[" index "]" | "[" string "]")*
+//! literal  := 'string' | "string" | number | true | false | null
+//! ```
+//!
+//! A path starts with the id of the step whose output it reads, so
+//! `check.status == 200` reads the `status` field of step `check`.
+//! Expressions nest at most [`MAX_DEPTH`] parentheses or `!`s deep and
+//! parse to at most [`MAX_NODES`] values and operators.
+//!
+//! The server may accept expressions this grammar does not, so
+//! [`Pipeline::validate`](crate::dsl::Pipeline::validate) never rejects a
+//! pipeline over its expressions; problems are reported by
+//! [`Pipeline::warnings`](crate::dsl::Pipeline::warnings) instead.
+//!
+//! The same condition can be built with [`Expr`]:
+//!
+//! ```
+//! use helios_client::expression::Expr;
+//!
+//! let expr = Expr::output("check").field("status").eq(200);
+//! assert_eq!(expr.to_string(), "check.status == 200");
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// How deeply parentheses and `!` may nest in a parsed expression.
+pub const MAX_DEPTH: usize = 64;
+
+/// How many values and operators a parsed expression may hold. Rendering,
+/// evaluating and dropping an [`Expr`] recurse over its tree, so a long
+/// `a && b && ...` chain is limited like deep nesting is.
+pub const MAX_NODES: usize = 1024;
+
+/// A syntax error in a `when` expression.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at offset {position}")]
+pub struct ExpressionError {
+    /// Byte offset into the expression.
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// One step into a step output: an object field or an array index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+/// A reference to a step output, or to a value inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub step: String,
+    pub segments: Vec<Segment>,
+}
+
+impl Path {
+    fn resolve<'a>(&self, outputs: &'a HashMap<String, Value>) -> Option<&'a Value> {
+        let mut value = outputs.get(&self.step)?;
+        for segment in &self.segments {
+            value = match segment {
+                Segment::Field(name) => value.get(name)?,
+                Segment::Index(index) => value.get(index)?,
+            };
+        }
+        Some(value)
+    }
+
+    pub fn field(mut self, name: &str) -> Self {
+        self.segments.push(Segment::Field(name.to_string()));
+        self
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(Segment::Index(index));
+        self
+    }
+
+    pub fn eq(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).eq(other)
+    }
+
+    pub fn ne(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).ne(other)
+    }
+
+    pub fn lt(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).lt(other)
+    }
+
+    pub fn le(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).le(other)
+    }
+
+    pub fn gt(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).gt(other)
+    }
+
+    pub fn ge(self, other: impl Into<Expr>) -> Expr {
+        Expr::from(self).ge(other)
+    }
+}
+
+/// A parsed `when` expression. `Display` renders the string form sent to
+/// the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Path(Path),
+    Compare {
+        op: CompareOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    pub fn parse(s: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser::new(s)?;
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(parser.error_at(token.position, "unexpected trailing input")),
+        }
+    }
+
+    /// The output of step `step_id`; narrow it with [`Path::field`] and
+    /// [`Path::index`].
+    pub fn output(step_id: &str) -> Path {
+        Path {
+            step: step_id.to_string(),
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn literal(value: impl Into<Value>) -> Self {
+        Expr::Literal(value.into())
+    }
+
+    pub fn eq(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Eq, other)
+    }
+
+    pub fn ne(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Ne, other)
+    }
+
+    pub fn lt(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Lt, other)
+    }
+
+    pub fn le(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Le, other)
+    }
+
+    pub fn gt(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Gt, other)
+    }
+
+    pub fn ge(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Ge, other)
+    }
+
+    pub fn and(self, other: impl Into<Expr>) -> Self {
+        Expr::And(Box::new(self), Box::new(other.into()))
+    }
+
+    pub fn or(self, other: impl Into<Expr>) -> Self {
+        Expr::Or(Box::new(self), Box::new(other.into()))
+    }
+
+    fn compare(self, op: CompareOp, other: impl Into<Expr>) -> Self {
+        Expr::Compare {
+            op,
+            left: Box::new(self),
+            right: Box::new(other.into()),
+        }
+    }
+
+    /// Ids of the steps the expression reads, without duplicates, in order
+    /// of first appearance.
+    pub fn step_refs(&self) -> Vec<&str> {
+        let mut refs = Vec::new();
+        self.collect_refs(&mut refs);
+        refs
+    }
+
+    fn collect_refs<'a>(&'a self, refs: &mut Vec<&'a str>) {
+        match self {
+            Expr::Literal(_) => {}
+            Expr::Path(path) => {
+                if !refs.contains(&path.step.as_str()) {
+                    refs.push(&path.step);
+                }
+            }
+            Expr::Compare { left, right, .. } | Expr::And(left, right) | Expr::Or(left, right) => {
+                left.collect_refs(refs);
+                right.collect_refs(refs);
+            }
+            Expr::Not(inner) => inner.collect_refs(refs),
+        }
+    }
+
+    /// Evaluates the condition against step outputs keyed by step id.
+    ///
+    /// Missing steps and fields read as `null`. `==` and `!=` compare JSON
+    /// values (numbers by value, so `1 == 1.0`); `<`, `<=`, `>`, `>=` compare
+    /// numbers or strings and are false for anything else. Used as a
+    /// condition, `null`, `false`, `0`, `""` and empty arrays or objects are
+    /// false.
+    pub fn evaluate(&self, outputs: &HashMap<String, Value>) -> bool {
+        truthy(&self.value(outputs))
+    }
+
+    fn value(&self, outputs: &HashMap<String, Value>) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Path(path) => path.resolve(outputs).cloned().unwrap_or(Value::Null),
+            Expr::Compare { op, left, right } => {
+                let (left, right) = (left.value(outputs), right.value(outputs));
+                let ordering = compare_values(&left, &right);
+                Value::Bool(match op {
+                    CompareOp::Eq => ordering == Some(Ordering::Equal) || left == right,
+                    CompareOp::Ne => !(ordering == Some(Ordering::Equal) || left == right),
+                    CompareOp::Lt => ordering == Some(Ordering::Less),
+                    CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    CompareOp::Gt => ordering == Some(Ordering::Greater),
+                    CompareOp::Ge => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                })
+            }
+            Expr::And(left, right) => {
+                Value::Bool(left.evaluate(outputs) && right.evaluate(outputs))
+            }
+            Expr::Or(left, right) => Value::Bool(left.evaluate(outputs) || right.evaluate(outputs)),
+            Expr::Not(inner) => Value::Bool(!inner.evaluate(outputs)),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Not(_) => 3,
+            Expr::Compare { .. } => 4,
+            Expr::Literal(_) | Expr::Path(_) => 5,
+        }
+    }
+
+    /// Writes `self`, parenthesized if it binds looser than `min`.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(Value::String(s)) => write_quoted(f, s),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Path(path) => write!(f, "{}", path),
+            Expr::Compare { op, left, right } => {
+                left.fmt_operand(f, 5)?;
+                write!(f, " {} ", op.as_str())?;
+                right.fmt_operand(f, 5)
+            }
+            Expr::And(left, right) => {
+                left.fmt_operand(f, 2)?;
+                f.write_str(" && ")?;
+                right.fmt_operand(f, 3)
+            }
+            Expr::Or(left, right) => {
+                left.fmt_operand(f, 1)?;
+                f.write_str(" || ")?;
+                right.fmt_operand(f, 2)
+            }
+            // `!a == b` already means `!(a == b)`, but spell it out.
+            Expr::Not(inner) => {
+                f.write_str("!")?;
+                inner.fmt_operand(f, 5)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.step)?;
+        for segment in &self.segments {
+            match segment {
+                Segment::Field(name) if is_identifier(name) => write!(f, ".{}", name)?,
+                Segment::Field(name) => {
+                    f.write_str("[")?;
+                    write_quoted(f, name)?;
+                    f.write_str("]")?;
+                }
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse(s)
+    }
+}
+
+impl From<Expr> for String {
+    fn from(expr: Expr) -> Self {
+        expr.to_string()
+    }
+}
+
+impl From<Path> for Expr {
+    fn from(path: Path) -> Self {
+        Expr::Path(path)
+    }
+}
+
+impl From<Value> for Expr {
+    fn from(value: Value) -> Self {
+        Expr::Literal(value)
+    }
+}
+
+macro_rules! literal_from {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Expr {
+            fn from(value: $t) -> Self {
+                Expr::Literal(Value::from(value))
+            }
+        })*
+    };
+}
+
+literal_from!(&str, String, bool, i32, i64, u32, u64, f64);
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("'")?;
+    for c in s.chars() {
+        if c == '\'' || c == '\\' {
+            f.write_str("\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    f.write_str("'")
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(is_identifier_start) && chars.all(is_identifier_char)
+}
+
+// --- Parser ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Str(String),
+    Number(Value),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    Dot,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ExpressionError> {
+    let error = |position: usize, message: String| ExpressionError { position, message };
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let two: String = s[position..].chars().take(2).collect();
+        let (kind, width) = match two.as_str() {
+            "&&" => (TokenKind::And, 2),
+            "||" => (TokenKind::Or, 2),
+            "==" => (TokenKind::Op(CompareOp::Eq), 2),
+            "!=" => (TokenKind::Op(CompareOp::Ne), 2),
+            "<=" => (TokenKind::Op(CompareOp::Le), 2),
+            ">=" => (TokenKind::Op(CompareOp::Ge), 2),
+            _ => match c {
+                '<' => (TokenKind::Op(CompareOp::Lt), 1),
+                '>' => (TokenKind::Op(CompareOp::Gt), 1),
+                '!' => (TokenKind::Not, 1),
+                '.' => (TokenKind::Dot, 1),
+                '(' => (TokenKind::LParen, 1),
+                ')' => (TokenKind::RParen, 1),
+                '[' => (TokenKind::LBracket, 1),
+                ']' => (TokenKind::RBracket, 1),
+                '\'' | '"' => {
+                    chars.next();
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '\\')) => match chars.next() {
+                                Some((_, escaped)) => value.push(escaped),
+                                None => {
+                                    return Err(error(position, "unterminated string".to_string()))
+                                }
+                            },
+                            Some((_, q)) if q == c => {
+                                tokens.push(Token {
+                                    kind: TokenKind::Str(value),
+                                    position,
+                                });
+                                break;
+                            }
+                            Some((_, other)) => value.push(other),
+                            None => return Err(error(position, "unterminated string".to_string())),
+                        }
+                    }
+                    continue;
+                }
+                c if c.is_ascii_digit() || c == '-' => {
+                    let end = s[position + 1..]
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                        .map_or(s.len(), |i| position + 1 + i);
+                    let literal = &s[position..end];
+                    let number = literal
+                        .parse::<i64>()
+                        .map(Value::from)
+                        .or_else(|_| literal.parse::<f64>().map(Value::from))
+                        .map_err(|_| error(position, format!("invalid number `{}`", literal)))?;
+                    (TokenKind::Number(number), end - position)
+                }
+                c if is_identifier_start(c) => {
+                    let end = s[position..]
+                        .find(|c: char| !is_identifier_char(c))
+                        .map_or(s.len(), |i| position + i);
+                    (
+                        TokenKind::Identifier(s[position..end].to_string()),
+                        end - position,
+                    )
+                }
+                other => return Err(error(position, format!("unexpected character `{}`", other))),
+            },
+        };
+        tokens.push(Token { kind, position });
+        let end = position + width;
+        while chars.peek().is_some_and(|&(i, _)| i < end) {
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    end: usize,
+    depth: usize,
+    nodes: usize,
+}
+
+impl Parser {
+    fn new(s: &str) -> Result<Self, ExpressionError> {
+        Ok(Parser {
+            tokens: tokenize(s)?,
+            next: 0,
+            end: s.len(),
+            depth: 0,
+            nodes: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| &t.kind == kind) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> ExpressionError {
+        ExpressionError {
+            position,
+            message: message.to_string(),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<(), ExpressionError> {
+        if self.eat(&kind) {
+            Ok(())
+        } else {
+            let position = self.peek().map_or(self.end, |t| t.position);
+            Err(self.error_at(position, &format!("expected {}", what)))
+        }
+    }
+
+    /// Counts a node for the token just consumed, failing past
+    /// [`MAX_NODES`].
+    fn count_node(&mut self) -> Result<(), ExpressionError> {
+        if self.nodes == MAX_NODES {
+            let position = self.tokens[self.next - 1].position;
+            return Err(self.error_at(position, "expression is too long"));
+        }
+        self.nodes += 1;
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.and()?;
+        while self.eat(&TokenKind::Or) {
+            self.count_node()?;
+            expr = expr.or(self.and()?);
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.not()?;
+        while self.eat(&TokenKind::And) {
+            self.count_node()?;
+            expr = expr.and(self.not()?);
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat(&TokenKind::Not) {
+            self.count_node()?;
+            let inner = self.nested(Self::not)?;
+            Ok(!inner)
+        } else {
+            self.compare()
+        }
+    }
+
+    /// Parses one nesting level deeper with `parse`, failing past
+    /// [`MAX_DEPTH`] instead of overflowing the stack.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Expr, ExpressionError>,
+    ) -> Result<Expr, ExpressionError> {
+        if self.depth == MAX_DEPTH {
+            let position = self.tokens[self.next - 1].position;
+            return Err(self.error_at(position, "expression is nested too deeply"));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn compare(&mut self) -> Result<Expr, ExpressionError> {
+        let left = self.operand()?;
+        if let Some(TokenKind::Op(op)) = self.peek().map(|t| t.kind.clone()) {
+            self.next += 1;
+            self.count_node()?;
+            let right = self.operand()?;
+            return Ok(left.compare(op, right));
+        }
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<Expr, ExpressionError> {
+        let Some(token) = self.advance() else {
+            return Err(self.error_at(self.end, "unexpected end of expression"));
+        };
+        if token.kind != TokenKind::LParen {
+            self.count_node()?;
+        }
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.nested(Self::or)?;
+                self.expect(TokenKind::RParen, "`)`")?;
+                Ok(expr)
+            }
+            TokenKind::Str(s) => Ok(Expr::literal(s)),
+            TokenKind::Number(n) => Ok(Expr::Literal(n)),
+            TokenKind::Identifier(name) => match name.as_str() {
+                "true" => Ok(Expr::literal(true)),
+                "false" => Ok(Expr::literal(false)),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => self.path(name),
+            },
+            _ => Err(self.error_at(token.position, "expected a value or step output")),
+        }
+    }
+
+    fn path(&mut self, step: String) -> Result<Expr, ExpressionError> {
+        let mut path = Expr::output(&step);
+        loop {
+            if self.eat(&TokenKind::Dot) {
+                match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Identifier(name),
+                        ..
+                    }) => path = path.field(&name),
+                    other => {
+                        let position = other.map_or(self.end, |t| t.position);
+                        return Err(self.error_at(position, "expected a field name after `.`"));
+                    }
+                }
+            } else if self.eat(&TokenKind::LBracket) {
+                match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Str(name),
+                        ..
+                    }) => path = path.field(&name),
+                    Some(Token {
+                        kind: TokenKind::Number(Value::Number(n)),
+                        position,
+                    }) => {
+                        let index = n.as_u64().ok_or_else(|| {
+                            self.error_at(position, "index must be a whole number")
+                        })?;
+                        path = path.index(index as usize);
+                    }
+                    other => {
+                        let position = other.map_or(self.end, |t| t.position);
+                        return Err(self.error_at(position, "expected an index or quoted field"));
+                    }
+                }
+                self.expect(TokenKind::RBracket, "`]`")?;
+            } else {
+                return Ok(path.into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_expressions_render_canonically_and_parse_back() {
+        let built = Expr::output("check")
+            .field("status")
+            .eq(200)
+            .and(!Expr::output("check").field("body").index(0).eq("it's"))
+            .or(Expr::output("flags").field("dry run"));
+
+        let rendered = built.to_string();
+
+        assert_eq!(
+            rendered,
+            r"check.status == 200 && !(check.body[0] == 'it\'s') || flags['dry run']"
+        );
+        assert_eq!(Expr::parse(&rendered).unwrap(), built);
+    }
+
+    #[test]
+    fn lists_step_refs_once_in_order() {
+        let expr = Expr::parse("check.a == 1 && flags.b || check.c").unwrap();
+        assert_eq!(expr.step_refs(), ["check", "flags"]);
+    }
+
+    #[test]
+    fn parsing_normalizes_quotes_and_keeps_needed_parentheses() {
+        let parsed = Expr::parse("a.x > -1.5 && (b == \"y\" || c.items[2] != null)").unwrap();
+        assert_eq!(
+            parsed.to_string(),
+            "a.x > -1.5 && (b == 'y' || c.items[2] != null)"
+        );
+    }
+
+    #[test]
+    fn syntax_errors_carry_their_offset() {
+        assert_eq!(Expr::parse("check.status ==").unwrap_err().position, 15);
+        assert!(Expr::parse("check.status = 200").is_err());
+        assert!(Expr::parse("'unterminated").is_err());
+    }
+
+    #[test]
+    fn trailing_backslashes_leave_strings_unterminated() {
+        for expr in ["a.b == 'x\\", "a.b == \"x\\"] {
+            let err = Expr::parse(expr).unwrap_err();
+            assert_eq!(err.message, "unterminated string");
+            assert_eq!(err.position, 7);
+        }
+    }
+
+    #[test]
+    fn evaluates_against_step_outputs() {
+        let outputs = HashMap::from([("check".to_string(), serde_json::json!({ "status": 200 }))]);
+        assert!(Expr::parse("check.status == 200.0")
+            .unwrap()
+            .evaluate(&outputs));
+        assert!(!Expr::parse("check.missing").unwrap().evaluate(&outputs));
+        assert!(Expr::parse("!(other.x > 1)").unwrap().evaluate(&outputs));
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_parses() {
+        let expr = format!("{}a{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(Expr::parse(&expr).unwrap(), Expr::output("a").into());
+        assert!(Expr::parse(&format!("{}a", "!".repeat(MAX_DEPTH))).is_ok());
+    }
+
+    #[test]
+    fn deeper_nesting_is_an_error_not_a_stack_overflow() {
+        for expr in [
+            format!("{}a", "(".repeat(100_000)),
+            format!("{}a", "!".repeat(100_000)),
+            format!("{}!a", "(".repeat(MAX_DEPTH)),
+        ] {
+            let err = Expr::parse(&expr).unwrap_err();
+            assert_eq!(err.message, "expression is nested too deeply");
+        }
+        let err = Expr::parse(&"(".repeat(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.position, MAX_DEPTH);
+    }
+
+    #[test]
+    fn the_longest_chains_render_evaluate_and_drop() {
+        // `a && a && ...` with MAX_NODES values and operators.
+        let chain = vec!["a"; MAX_NODES.div_ceil(2)].join(" && ");
+        let expr = Expr::parse(&chain).unwrap();
+        assert_eq!(expr.to_string(), chain);
+        assert_eq!(expr.step_refs(), ["a"]);
+        assert!(!expr.evaluate(&HashMap::new()));
+    }
+
+    #[test]
+    fn longer_expressions_are_an_error_not_a_stack_overflow() {
+        let chain = vec!["a"; 100_000].join(" && ");
+        let err = Expr::parse(&chain).unwrap_err();
+        assert_eq!(err.message, "expression is too long");
+        // The first value past the limit: MAX_NODES / 2 values and as many
+        // `&&`s come before it.
+        assert_eq!(err.position, MAX_NODES / 2 * "a && ".len());
+    }
+}
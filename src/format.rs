@@ -0,0 +1,375 @@
+//! Pipeline definition files.
+//!
+//! A [`Pipeline`] can be stored as JSON, YAML (`yaml` feature) or TOML (`toml`
+//! feature). All three share one layout, shown here in YAML:
+//!
+//! ```yaml
+//! name: weather-report          # required
+//! trigger: "cron:0 6 * * MON"   # optional; see `crate::trigger` for the encoding
+//! steps:                        # required, in declaration order
+//!   - id: fetch                 # required unless `name` is given
+//!     type: webhook             # webhook | script | nimbus_container | llm_workflow | event
+//!     data:                     # step configuration, as produced by the builders
+//!       url: https://api.example.com/weather
+//!       method: GET
+//!   - id: summarize
+//!     type: llm_workflow
+//!     target: generic           # optional
+//!     depends_on: [fetch]       # optional; a single id or a list
+//!     expression: "..."         # optional; `when` is accepted as an alias
+//!     data:
+//!       prompt: "Summarize {{nested_json 'fetch'}}"
+//! outputs: [summarize]          # optional
+//! ```
+//!
+//! `name` and `key` default to the step id and are only written out when they
+//! differ from it. `data` defaults to an empty object. Unknown fields are
+//! rejected so typos surface when the file is loaded.
+//!
+//! TOML has no null: when writing TOML, `null` members of `data` objects are
+//! left out, and a `null` array element fails with `FormatError::TomlNull`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::dsl::{ActionType, Pipeline, Step};
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("failed to read pipeline file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON pipeline definition: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "yaml")]
+    #[error("invalid YAML pipeline definition: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[cfg(feature = "toml")]
+    #[error("invalid TOML pipeline definition: {0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[cfg(feature = "toml")]
+    #[error("failed to write TOML pipeline definition: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+    #[cfg(feature = "toml")]
+    #[error("step `{step}` has a null array element at `data{path}`, which TOML cannot represent")]
+    TomlNull { step: String, path: String },
+    #[error("unsupported pipeline file format `{0}`")]
+    UnsupportedFormat(String),
+}
+
+/// Serialization formats for pipeline definition files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    /// Picks the format from a file extension (`json`, `yaml`/`yml`, `toml`).
+    pub fn from_path(path: &Path) -> Result<Self, FormatError> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Ok(Format::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Ok(Format::Yaml),
+            #[cfg(feature = "toml")]
+            "toml" => Ok(Format::Toml),
+            other => Err(FormatError::UnsupportedFormat(other.to_string())),
+        }
+    }
+}
+
+impl Pipeline {
+    pub fn from_json_str(s: &str) -> Result<Self, FormatError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Serializes the pipeline as pretty-printed JSON.
+    pub fn to_json_string(&self) -> Result<String, FormatError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(s: &str) -> Result<Self, FormatError> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml_string(&self) -> Result<String, FormatError> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self, FormatError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Serializes the pipeline as TOML, leaving out `null` members of step
+    /// `data`.
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, FormatError> {
+        let mut pipeline = self.clone();
+        for step in &mut pipeline.steps {
+            strip_nulls(&mut step.data, "").map_err(|path| FormatError::TomlNull {
+                step: step.effective_id().to_string(),
+                path,
+            })?;
+        }
+        Ok(toml::to_string_pretty(&pipeline)?)
+    }
+
+    pub fn from_str_as(s: &str, format: Format) -> Result<Self, FormatError> {
+        match format {
+            Format::Json => Self::from_json_str(s),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml_str(s),
+            #[cfg(feature = "toml")]
+            Format::Toml => Self::from_toml_str(s),
+        }
+    }
+
+    pub fn to_string_as(&self, format: Format) -> Result<String, FormatError> {
+        match format {
+            Format::Json => self.to_json_string(),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => self.to_yaml_string(),
+            #[cfg(feature = "toml")]
+            Format::Toml => self.to_toml_string(),
+        }
+    }
+
+    /// Loads a pipeline file, picking the format from its extension.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        let path = path.as_ref();
+        let format = Format::from_path(path)?;
+        Self::from_str_as(&std::fs::read_to_string(path)?, format)
+    }
+
+    /// Writes a pipeline file, picking the format from its extension.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), FormatError> {
+        let path = path.as_ref();
+        let format = Format::from_path(path)?;
+        std::fs::write(path, self.to_string_as(format)?)?;
+        Ok(())
+    }
+}
+
+/// On-disk form of a [`Step`].
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StepRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(rename = "type")]
+    type_: ActionType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    depends_on: Vec<String>,
+    #[serde(default, alias = "when", skip_serializing_if = "Option::is_none")]
+    expression: Option<String>,
+    #[serde(default = "empty_object")]
+    data: Value,
+}
+
+impl TryFrom<StepRepr> for Step {
+    type Error = String;
+
+    fn try_from(repr: StepRepr) -> Result<Self, Self::Error> {
+        let name = repr
+            .name
+            .or_else(|| repr.id.clone())
+            .ok_or_else(|| "step requires an `id` or a `name`".to_string())?;
+        Ok(Step {
+            key: repr.key.unwrap_or_else(|| name.clone()),
+            id: repr.id,
+            name,
+            type_: repr.type_,
+            data: repr.data,
+            target: repr.target,
+            depends_on: repr.depends_on,
+            expression: repr.expression,
+        })
+    }
+}
+
+impl From<Step> for StepRepr {
+    fn from(step: Step) -> Self {
+        let name = (step.id.as_deref() != Some(step.name.as_str())).then(|| step.name.clone());
+        let key = (step.key != step.name).then_some(step.key);
+        StepRepr {
+            id: step.id,
+            name,
+            key,
+            type_: step.type_,
+            target: step.target,
+            depends_on: step.depends_on,
+            expression: step.expression,
+            data: step.data,
+        }
+    }
+}
+
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+/// Removes `null` object members from `value`, failing with the JSON
+/// Pointer of the first `null` array element.
+#[cfg(feature = "toml")]
+fn strip_nulls(value: &mut Value, path: &str) -> Result<(), String> {
+    match value {
+        Value::Object(object) => {
+            object.retain(|_, member| !member.is_null());
+            for (key, member) in object.iter_mut() {
+                strip_nulls(member, &format!("{}/{}", path, key))?;
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let path = format!("{}/{}", path, index);
+                if item.is_null() {
+                    return Err(path);
+                }
+                strip_nulls(item, &path)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(id) => vec![id],
+        OneOrMany::Many(ids) => ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::PipelineBuilder;
+    use crate::trigger::Trigger;
+    use std::path::PathBuf;
+
+    fn pipeline() -> Pipeline {
+        PipelineBuilder::new("report")
+            .trigger(Trigger::cron("0 6 * * MON"))
+            .webhook("fetch", "https://api.example.com/weather")
+            .then()
+            .script("summarize", "echo done")
+            .depends_on("fetch")
+            .when("fetch != ''")
+            .then()
+            .output("summarize")
+            .build()
+    }
+
+    /// A path in the temp directory that is removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let name = format!("helios-format-{}-{}", std::process::id(), name);
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn round_trip(file: &str) -> Pipeline {
+        let path = TempFile::new(file);
+        pipeline().to_file(&path.0).unwrap();
+        Pipeline::from_file(&path.0).unwrap()
+    }
+
+    fn assert_same(a: &Pipeline, b: &Pipeline) {
+        assert_eq!(a.to_json_string().unwrap(), b.to_json_string().unwrap());
+    }
+
+    #[test]
+    fn json_files_round_trip() {
+        assert_same(&round_trip("pipeline.json"), &pipeline());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_files_round_trip() {
+        assert_same(&round_trip("pipeline.yaml"), &pipeline());
+        assert_same(&round_trip("pipeline.yml"), &pipeline());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_files_round_trip() {
+        assert_same(&round_trip("pipeline.toml"), &pipeline());
+    }
+
+    #[test]
+    fn unknown_extensions_are_rejected() {
+        let err = pipeline().to_file("pipeline.txt").unwrap_err();
+        assert!(matches!(err, FormatError::UnsupportedFormat(ext) if ext == "txt"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_leaves_out_null_members() {
+        let mut pipeline = pipeline();
+        pipeline.steps[0].data =
+            serde_json::json!({ "url": "https://x", "body": null, "nested": { "a": null } });
+
+        let written = Pipeline::from_toml_str(&pipeline.to_toml_string().unwrap()).unwrap();
+
+        assert_eq!(
+            written.steps[0].data,
+            serde_json::json!({ "url": "https://x", "nested": {} })
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_rejects_null_array_elements() {
+        let mut pipeline = pipeline();
+        pipeline.steps[1].data = serde_json::json!({ "args": ["a", null] });
+
+        let err = pipeline.to_toml_string().unwrap_err();
+
+        assert!(
+            matches!(&err, FormatError::TomlNull { step, path } if step == "summarize" && path == "/args/1"),
+            "{}",
+            err
+        );
+    }
+}
@@ -1,21 +1,50 @@
+pub mod builder;
 pub mod client;
+pub mod container;
+pub mod diagram;
 pub mod dsl;
+pub mod error;
+pub mod expression;
+pub mod format;
+pub mod llm;
+pub mod local;
+pub mod plan;
+pub mod retry;
+pub mod run_result;
+pub mod schema;
+pub mod template;
+pub mod trigger;
+pub mod validation;
+pub mod webhook;
 
 pub mod create_pipeline;
+pub mod hello;
+pub mod pipeline_updates;
 pub mod run_pipeline;
+#[cfg(feature = "subscriptions")]
+pub mod subscription;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub type JSON = serde_json::Value;
 use graphql_client::GraphQLQuery;
 
 use crate::create_pipeline::create_pipeline::{
     CreatePipelineCreatePipeline, Variables as CreatePipelineVariables,
 };
-use crate::run_pipeline::run_pipeline::{
-    RunPipelineRunPipeline, Variables as RunPipelineVariables,
-};
-use client::{CreatePipelineResponseData, RunPipelineResponseData};
+use crate::run_pipeline::run_pipeline::Variables as RunPipelineVariables;
+pub use builder::{Auth, HeliosClientBuilder};
+use client::{CreatePipelineResponseData, HelloResponseData, RunPipelineResponseData};
 use dsl::Pipeline;
-use reqwest::Client;
+pub use error::{GraphQLError, GraphQLErrors};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+pub use retry::{RetryPolicy, IDEMPOTENCY_KEY_HEADER, TRANSIENT_STATUSES};
+pub use run_result::{PipelineRun, StepError};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
 use thiserror::Error;
+pub use trigger::Trigger;
+use validation::ValidationErrors;
 
 // Remove GraphQLQuery import, not needed for generated modules
 use graphql_client::Response as GraphQLResponse; // GraphQL types
@@ -24,22 +53,110 @@ use graphql_client::Response as GraphQLResponse; // GraphQL types
 pub enum HeliosClientError {
     #[error("HTTP request failed: {0}")]
     Request(#[from] reqwest::Error),
-    #[error("GraphQL request failed: {0:?}")]
-    GraphQL(Vec<graphql_client::Error>),
+    #[error("HTTP error: {status} - {body}")]
+    HttpStatus { status: StatusCode, body: String },
+    #[error("GraphQL request failed: {0}")]
+    GraphQL(GraphQLErrors),
     #[error("Failed to deserialize response: {0}")]
     Deserialization(#[from] serde_json::Error),
     #[error("No data received from GraphQL response")]
     NoData,
     #[error("Invalid response format")]
     InvalidResponseFormat,
-    #[error("Invalid pipeline input: {0}")]
-    GenericError(String),
+    #[error("Pipeline failed: {message}{}", format_step_errors(.step_errors))]
+    PipelineFailed {
+        message: String,
+        step_errors: Vec<StepError>,
+    },
+    #[error("No output for step `{0}`")]
+    MissingOutput(String),
+    #[error("Output of step `{step_id}` does not match its schema: {}", schema::describe(.violations))]
+    OutputSchema {
+        step_id: String,
+        violations: Vec<schema::SchemaViolation>,
+    },
+    #[error("Invalid pipeline: {0}")]
+    InvalidPipeline(#[from] ValidationErrors),
+    #[error("Pipelines without triggers must be run directly, not created.")]
+    MissingTrigger,
+    #[error("Invalid client configuration: {0}")]
+    InvalidConfig(String),
+    #[cfg(feature = "subscriptions")]
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    #[cfg(feature = "subscriptions")]
+    #[error("Subscription protocol error: {0}")]
+    Subscription(String),
+}
+
+/// Validates `pipeline` before it is sent, logging its
+/// [`warnings`](Pipeline::warnings).
+pub(crate) fn check_pipeline(pipeline: &Pipeline) -> Result<(), HeliosClientError> {
+    pipeline.validate()?;
+    for warning in pipeline.warnings() {
+        tracing::warn!(pipeline = %pipeline.name, "{}", warning);
+    }
+    Ok(())
+}
+
+fn format_step_errors(errors: &[StepError]) -> String {
+    errors.iter().map(|e| format!("\n  - {}", e)).collect()
+}
+
+impl HeliosClientError {
+    /// Whether the failure is transient, so repeating the same request may
+    /// succeed: connection failures, timeouts, and the
+    /// [`TRANSIENT_STATUSES`].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HeliosClientError::Request(e) => e.is_connect() || e.is_timeout(),
+            HeliosClientError::HttpStatus { status, .. } => {
+                retry::TRANSIENT_STATUSES.contains(status)
+            }
+            #[cfg(feature = "subscriptions")]
+            HeliosClientError::WebSocket(e) => matches!(
+                e,
+                tokio_tungstenite::tungstenite::Error::Io(_)
+                    | tokio_tungstenite::tungstenite::Error::ConnectionClosed
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the request itself was at fault and must be changed before it
+    /// can succeed: 4xx statuses, invalid pipelines or configuration, and
+    /// GraphQL errors whose codes all blame the request.
+    pub fn is_client_error(&self) -> bool {
+        match self {
+            HeliosClientError::HttpStatus { status, .. } => status.is_client_error(),
+            HeliosClientError::GraphQL(errors) => errors.is_client_error(),
+            HeliosClientError::InvalidPipeline(_)
+            | HeliosClientError::MissingTrigger
+            | HeliosClientError::InvalidConfig(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The HTTP status, for errors caused by a non-2xx response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            HeliosClientError::HttpStatus { status, .. } => Some(*status),
+            HeliosClientError::Request(e) => e.status(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone)] // Add clone if needed
 pub struct HeliosGraphQLClient {
     endpoint: String,
     http_client: Client,
+    // Default headers (auth, user agent, extras), reused for subscriptions.
+    #[cfg_attr(not(feature = "subscriptions"), allow(dead_code))]
+    headers: HeaderMap,
+    #[cfg_attr(not(feature = "subscriptions"), allow(dead_code))]
+    connect_timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl HeliosGraphQLClient {
@@ -48,9 +165,18 @@ impl HeliosGraphQLClient {
         Self {
             endpoint,
             http_client: Client::new(), // Create a reqwest client
+            headers: HeaderMap::new(),
+            connect_timeout: None,
+            retry_policy: None,
         }
     }
 
+    /// Starts configuring a client with auth, timeouts, headers, proxy or
+    /// TLS settings.
+    pub fn builder(endpoint: &str) -> HeliosClientBuilder {
+        HeliosClientBuilder::new(endpoint)
+    }
+
     /// Connect (alternative constructor, maybe less needed now)
     pub async fn connect(endpoint: &str) -> Result<Self, HeliosClientError> {
         // Basic connect, no TLS specific config here, reqwest handles defaults
@@ -58,76 +184,123 @@ impl HeliosGraphQLClient {
     }
 
     /// Runs the pipeline via GraphQL mutation.
-    pub async fn run_pipeline(
-        &self,
-        pipeline: Pipeline,
-    ) -> Result<RunPipelineRunPipeline, HeliosClientError> {
-        if pipeline.trigger.is_some() {
-            return Err(HeliosClientError::GenericError(
-                "Pipelines with triggers must be created, not run directly.".to_string(),
-            ));
+    ///
+    /// A run that completes with failed steps is still `Ok`; use
+    /// [`PipelineRun::into_result`] to treat it as an error.
+    ///
+    /// The pipeline is validated first; an invalid pipeline is rejected with
+    /// [`HeliosClientError::InvalidPipeline`] without contacting the server;
+    /// its [`warnings`](Pipeline::warnings) are logged.
+    /// A trigger, if set, is not sent and a warning is logged: the pipeline
+    /// runs once, immediately. Use [`Self::create_pipeline`] to register it.
+    /// LLM results are checked against their steps' output schemas; see
+    /// [`PipelineRun::check_output_schemas`].
+    ///
+    /// Each call carries a new [`IDEMPOTENCY_KEY_HEADER`], shared by its
+    /// retries, so a retried request does not start the run twice while
+    /// separate calls still run the pipeline again.
+    pub async fn run_pipeline(&self, pipeline: Pipeline) -> Result<PipelineRun, HeliosClientError> {
+        check_pipeline(&pipeline)?;
+        if let Some(trigger) = &pipeline.trigger {
+            tracing::warn!(
+                pipeline = %pipeline.name,
+                trigger = trigger.kind(),
+                "run_pipeline ignores the pipeline trigger; use create_pipeline to register it"
+            );
         }
+        let output_schemas = pipeline.output_schemas();
 
         let variables = RunPipelineVariables {
             pipeline: pipeline.into(),
         };
 
         let request_body = crate::run_pipeline::RunPipeline::build_query(variables);
+        let body = serde_json::to_vec(&request_body)?;
 
-        let res = self
-            .http_client
-            .post(&self.endpoint)
-            .json(&request_body)
-            .send()
-            .await?;
+        let idempotency_key = retry::unique_idempotency_key();
 
-        let status = res.status();
-        if !status.is_success() {
-            let error_body = res
-                .text()
-                .await
-                .unwrap_or_else(|_| "Failed to read error body".to_string());
-
-            return Err(HeliosClientError::GenericError(format!(
-                "HTTP error: {} - {}",
-                status, error_body
-            )));
-        }
-
-        let response_body: GraphQLResponse<RunPipelineResponseData> = res.json().await?;
-
-        if let Some(errors) = response_body.errors {
-            return Err(HeliosClientError::GraphQL(errors));
-        }
-
-        let response_data = response_body.data.ok_or(HeliosClientError::NoData)?;
+        let response_data: RunPipelineResponseData =
+            self.execute(body, Some(&idempotency_key)).await?;
         let run_pipeline_data = response_data.run_pipeline;
 
-        Ok(run_pipeline_data)
+        Ok(PipelineRun::from(run_pipeline_data).check_output_schemas(&output_schemas))
     }
 
+    /// Registers a pipeline with a trigger via GraphQL mutation.
+    ///
+    /// The pipeline is validated first, as in [`Self::run_pipeline`].
+    /// Requests carry an [`IDEMPOTENCY_KEY_HEADER`] derived from the pipeline
+    /// definition, so a retried request does not register a duplicate.
     pub async fn create_pipeline(
         &self,
         pipeline: Pipeline,
     ) -> Result<CreatePipelineCreatePipeline, HeliosClientError> {
         if pipeline.trigger.is_none() {
-            return Err(HeliosClientError::GenericError(
-                "Pipelines without triggers must be run directly, not created.".to_string(),
-            ));
+            return Err(HeliosClientError::MissingTrigger);
         }
+        check_pipeline(&pipeline)?;
 
         let variables = CreatePipelineVariables {
-            pipeline: pipeline.into(),
+            pipeline: pipeline.try_into()?,
         };
 
         let request_body = crate::create_pipeline::CreatePipeline::build_query(variables);
+        let body = serde_json::to_vec(&request_body)?;
+        let idempotency_key = retry::idempotency_key(&body);
+
+        let response_data: CreatePipelineResponseData =
+            self.execute(body, Some(&idempotency_key)).await?;
+        let create_pipeline_data = response_data.create_pipeline;
+
+        Ok(create_pipeline_data)
+    }
+
+    /// Runs the `hello` query. Useful as a connectivity and auth check.
+    pub async fn hello(&self) -> Result<String, HeliosClientError> {
+        let request_body = crate::hello::Hello::build_query(crate::hello::hello::Variables);
+        let body = serde_json::to_vec(&request_body)?;
+
+        let response_data: HelloResponseData = self.execute(body, None).await?;
+
+        Ok(response_data.hello)
+    }
 
-        let res = self
-            .http_client
-            .post(&self.endpoint)
-            .json(&request_body)
-            .send()
-            .await?;
+    /// Posts a serialized GraphQL request, retrying transient failures
+    /// according to the client's [`RetryPolicy`], and unwraps the response.
+    async fn execute<T: DeserializeOwned>(
+        &self,
+        body: Vec<u8>,
+        idempotency_key: Option<&str>,
+    ) -> Result<T, HeliosClientError> {
+        let mut attempt = 1;
+        let res = loop {
+            let mut request = self
+                .http_client
+                .post(&self.endpoint)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(key) = idempotency_key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+
+            let policy = self
+                .retry_policy
+                .as_ref()
+                .filter(|p| attempt < p.max_attempts);
+            let delay = match (request.send().await, policy) {
+                (Ok(res), Some(policy)) if policy.should_retry_status(res.status()) => {
+                    tracing::warn!(attempt, status = %res.status(), "retrying GraphQL request");
+                    policy.backoff(attempt, Some(res.headers()))
+                }
+                (Err(e), Some(policy)) if policy.should_retry_error(&e) => {
+                    tracing::warn!(attempt, error = %e, "retrying GraphQL request");
+                    policy.backoff(attempt, None)
+                }
+                (res, _) => break res?,
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
 
         let status = res.status();
         if !status.is_success() {
@@ -136,21 +309,18 @@ impl HeliosGraphQLClient {
                 .await
                 .unwrap_or_else(|_| "Failed to read error body".to_string());
 
-            return Err(HeliosClientError::GenericError(format!(
-                "HTTP error: {} - {}",
-                status, error_body
-            )));
+            return Err(HeliosClientError::HttpStatus {
+                status,
+                body: error_body,
+            });
         }
 
-        let response_body: GraphQLResponse<CreatePipelineResponseData> = res.json().await?;
+        let response_body: GraphQLResponse<T> = res.json().await?;
 
         if let Some(errors) = response_body.errors {
-            return Err(HeliosClientError::GraphQL(errors));
+            return Err(HeliosClientError::GraphQL(errors.into()));
         }
 
-        let response_data = response_body.data.ok_or(HeliosClientError::NoData)?;
-        let create_pipeline_data = response_data.create_pipeline;
-
-        Ok(create_pipeline_data)
+        response_body.data.ok_or(HeliosClientError::NoData)
     }
 }
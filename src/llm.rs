@@ -0,0 +1,575 @@
+//! Typed configuration for `llm_workflow` steps.
+//!
+//! [`LlmSpec`] mirrors the step's `data` object, so it can be read from and
+//! written back to an existing [`Step`](crate::dsl::Step) without losing keys
+//! it does not model.
+//!
+//! Agent workflows (`agent_executor`) can call [`Tool`]s, each bound to a
+//! webhook or container the server runs when the agent invokes it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::webhook::HttpMethod;
+
+/// Accepted `temperature` range.
+pub const TEMPERATURE_RANGE: std::ops::RangeInclusive<f64> = 0.0..=2.0;
+
+/// Which LLM provider serves the step. Providers without a variant can be
+/// named with [`Provider::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    OpenAi,
+    Anthropic,
+    Google,
+    AzureOpenAi,
+    Bedrock,
+    Ollama,
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// The `data` of an `llm_workflow` step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LlmSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sampling temperature, within [`TEMPERATURE_RANGE`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling probability mass, in `(0, 1]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// Upper bound on generated tokens; must be positive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end generation; must not be empty strings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// JSON Schema the step result must match; see [`crate::schema`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    /// Tools an agent workflow may call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    /// Routes of a `routing` workflow.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<Route>,
+    /// Step taken by a `routing` workflow when no route applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_route: Option<String>,
+    /// Workers of an `orchestrator` workflow.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workers: Vec<Worker>,
+    /// Prompts of a `prompt_chain` workflow, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompts: Vec<String>,
+    /// Criteria an `evaluator_optimizer` workflow checks drafts against.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<String>,
+    /// Revision limit of an `evaluator_optimizer` workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    /// Keys not modelled above, kept as-is.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LlmSpec {
+    /// Reads a spec from a step's `data`.
+    pub fn from_data(data: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(data)
+    }
+
+    /// The `data` object for this spec.
+    pub fn to_data(&self) -> Value {
+        serde_json::to_value(self).expect("LLM spec serializes to JSON")
+    }
+
+    /// Every text rendered as a prompt template, with the field it is set
+    /// in: `system_prompt`, `prompt`, the chained `prompts`, then worker
+    /// instructions and route conditions.
+    pub fn templates(&self) -> impl Iterator<Item = (String, &str)> {
+        let system_prompt = self.system_prompt.as_deref();
+        let prompt = self.prompt.as_deref();
+        system_prompt
+            .map(|t| ("system_prompt".to_string(), t))
+            .into_iter()
+            .chain(prompt.map(|t| ("prompt".to_string(), t)))
+            .chain(
+                self.prompts
+                    .iter()
+                    .map(|t| ("prompts".to_string(), t.as_str())),
+            )
+            .chain(self.workers.iter().map(|w| {
+                (
+                    format!("workers.{}.instructions", w.name),
+                    w.instructions.as_str(),
+                )
+            }))
+            .chain(self.routes.iter().map(|r| {
+                (
+                    format!("routes.{}.condition", r.target),
+                    r.condition.as_str(),
+                )
+            }))
+    }
+}
+
+/// A route of a `routing` workflow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    /// When to take the route, in natural language for the model to judge.
+    pub condition: String,
+    /// Id of the step the route leads to.
+    pub target: String,
+}
+
+impl Route {
+    pub fn new(condition: &str, target: &str) -> Self {
+        Self {
+            condition: condition.to_string(),
+            target: target.to_string(),
+        }
+    }
+}
+
+/// A worker an `orchestrator` workflow can hand subtasks to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worker {
+    pub name: String,
+    /// What the worker does, shown to the orchestrator and the worker.
+    pub instructions: String,
+    /// Overrides the step's provider for this worker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    /// Overrides the step's model for this worker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Worker {
+    pub fn new(name: &str, instructions: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            instructions: instructions.to_string(),
+            provider: None,
+            model: None,
+        }
+    }
+
+    pub fn provider(mut self, provider: Provider) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+}
+
+/// What runs when an agent calls a tool. Serialized with a `type` key
+/// naming the step type, followed by that step type's settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolBinding {
+    /// Sends the call arguments as the JSON body of a request to `url`.
+    #[serde(rename = "webhook")]
+    Webhook {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        method: Option<HttpMethod>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// Runs `image` with the call arguments on standard input.
+    #[serde(rename = "nimbus_container")]
+    Container {
+        image: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+    },
+}
+
+impl ToolBinding {
+    /// The `type` tags of the known bindings.
+    pub const TYPES: &'static [&'static str] = &["webhook", "nimbus_container"];
+}
+
+/// A tool an agent may call: the name and description shown to the model,
+/// a JSON Schema for its arguments, and the binding that serves it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    /// Letters, digits, `_` and `-`; at most 64 characters.
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the arguments; must describe an object.
+    pub parameters: Value,
+    pub binding: ToolBinding,
+}
+
+impl Tool {
+    /// A tool served by a webhook, taking no arguments until
+    /// [`parameters`](Self::parameters) is set.
+    pub fn webhook(name: &str, description: &str, url: &str) -> Self {
+        Self::new(
+            name,
+            description,
+            ToolBinding::Webhook {
+                url: url.to_string(),
+                method: None,
+                headers: BTreeMap::new(),
+            },
+        )
+    }
+
+    /// A tool served by a container, taking no arguments until
+    /// [`parameters`](Self::parameters) is set.
+    pub fn container(name: &str, description: &str, image: &str) -> Self {
+        Self::new(
+            name,
+            description,
+            ToolBinding::Container {
+                image: image.to_string(),
+                command: None,
+                env: BTreeMap::new(),
+            },
+        )
+    }
+
+    pub fn new(name: &str, description: &str, binding: ToolBinding) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: serde_json::json!({ "type": "object", "properties": {} }),
+            binding,
+        }
+    }
+
+    /// Sets the JSON Schema of the arguments.
+    pub fn parameters(mut self, schema: Value) -> Self {
+        self.parameters = schema;
+        self
+    }
+
+    /// Derives the argument schema from `T`.
+    #[cfg(feature = "schema")]
+    pub fn parameters_for<T: schemars::JsonSchema>(self) -> Self {
+        let schema =
+            serde_json::to_value(schemars::schema_for!(T)).expect("JSON Schema serializes to JSON");
+        self.parameters(schema)
+    }
+
+    /// Sets the HTTP method of a webhook binding; has no effect on
+    /// containers.
+    pub fn method(mut self, method: HttpMethod) -> Self {
+        if let ToolBinding::Webhook { method: m, .. } = &mut self.binding {
+            *m = Some(method);
+        }
+        self
+    }
+
+    /// Adds a header to a webhook binding; has no effect on containers.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        if let ToolBinding::Webhook { headers, .. } = &mut self.binding {
+            headers.insert(name.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Sets an environment variable of a container binding; has no effect
+    /// on webhooks.
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        if let ToolBinding::Container { env, .. } = &mut self.binding {
+            env.insert(name.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Sets the command of a container binding; has no effect on webhooks.
+    pub fn command(mut self, args: &[&str]) -> Self {
+        if let ToolBinding::Container { command, .. } = &mut self.binding {
+            *command = Some(args.iter().map(|a| a.to_string()).collect());
+        }
+        self
+    }
+}
+
+/// A set of tools shared by several agent steps, added with
+/// [`LlmWorkflowStepBuilder::tools`](crate::dsl::LlmWorkflowStepBuilder::tools).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `tool`, replacing any tool with the same name.
+    pub fn register(mut self, tool: Tool) -> Self {
+        self.tools.retain(|t| t.name != tool.name);
+        self.tools.push(tool);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tool> {
+        self.tools.iter().find(|t| t.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Tool> {
+        self.tools.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{LLMWorkflowTypes, PipelineBuilder};
+    use serde_json::json;
+
+    #[test]
+    fn generation_settings_serialize_into_step_data() {
+        let pipeline = PipelineBuilder::new("llm")
+            .llm_workflow("answer")
+            .prompt("Hello")
+            .system_prompt("Be brief.")
+            .provider(Provider::Anthropic)
+            .model("claude-sonnet")
+            .temperature(0.2)
+            .top_p(0.9)
+            .max_tokens(256)
+            .stop_sequence("\n\n")
+            .then()
+            .build();
+
+        assert_eq!(
+            pipeline.steps[0].data,
+            json!({
+                "prompt": "Hello",
+                "system_prompt": "Be brief.",
+                "provider": "anthropic",
+                "model": "claude-sonnet",
+                "temperature": 0.2,
+                "top_p": 0.9,
+                "max_tokens": 256,
+                "stop_sequences": ["\n\n"],
+            })
+        );
+    }
+
+    #[test]
+    fn other_providers_serialize_by_name() {
+        let provider = Provider::Other("vllm".to_string());
+        assert_eq!(serde_json::to_value(&provider).unwrap(), json!("vllm"));
+        assert_eq!(
+            serde_json::from_value::<Provider>(json!("vllm")).unwrap(),
+            provider
+        );
+        assert_eq!(
+            serde_json::from_value::<Provider>(json!("open_ai")).unwrap(),
+            Provider::OpenAi
+        );
+    }
+
+    #[test]
+    fn templates_list_the_prompt_then_chained_prompts() {
+        let spec = LlmSpec::from_data(&json!({ "prompt": "a", "prompts": ["b", "c"] })).unwrap();
+        let templates: Vec<_> = spec.templates().map(|(_, t)| t).collect();
+        assert_eq!(templates, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn templates_include_system_prompts_instructions_and_conditions() {
+        let spec = LlmSpec::from_data(&json!({
+            "system_prompt": "s",
+            "prompt": "p",
+            "workers": [{ "name": "writer", "instructions": "w" }],
+            "routes": [{ "condition": "c", "target": "refund" }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            spec.templates().collect::<Vec<_>>(),
+            [
+                ("system_prompt".to_string(), "s"),
+                ("prompt".to_string(), "p"),
+                ("workers.writer.instructions".to_string(), "w"),
+                ("routes.refund.condition".to_string(), "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn webhook_tools_serialize_with_their_binding() {
+        let tool = Tool::webhook("lookup_order", "Find an order", "https://shop/orders")
+            .method(HttpMethod::Post)
+            .header("authorization", "{{secrets.shop_token}}")
+            .parameters(json!({ "type": "object", "required": ["order_id"] }));
+
+        assert_eq!(
+            serde_json::to_value(&tool).unwrap(),
+            json!({
+                "name": "lookup_order",
+                "description": "Find an order",
+                "parameters": { "type": "object", "required": ["order_id"] },
+                "binding": {
+                    "type": "webhook",
+                    "url": "https://shop/orders",
+                    "method": "POST",
+                    "headers": { "authorization": "{{secrets.shop_token}}" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn container_tools_default_to_no_parameters() {
+        let tool = Tool::container("resize", "Resize an image", "imagemagick:7")
+            .command(&["convert"])
+            .env("QUALITY", "80");
+
+        assert_eq!(
+            serde_json::to_value(&tool).unwrap(),
+            json!({
+                "name": "resize",
+                "description": "Resize an image",
+                "parameters": { "type": "object", "properties": {} },
+                "binding": {
+                    "type": "nimbus_container",
+                    "image": "imagemagick:7",
+                    "command": ["convert"],
+                    "env": { "QUALITY": "80" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn binding_types_match_the_serialized_tags() {
+        let bindings = [
+            Tool::webhook("a", "", "https://x").binding,
+            Tool::container("b", "", "alpine").binding,
+        ];
+        let tags: Vec<_> = bindings
+            .iter()
+            .map(|b| serde_json::to_value(b).unwrap()["type"].clone())
+            .collect();
+        assert_eq!(tags, ToolBinding::TYPES);
+    }
+
+    #[test]
+    fn registry_replaces_tools_by_name() {
+        let registry = ToolRegistry::new()
+            .register(Tool::webhook("lookup", "old", "https://a"))
+            .register(Tool::container("resize", "Resize", "alpine"))
+            .register(Tool::webhook("lookup", "new", "https://b"));
+
+        let names: Vec<_> = registry.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["resize", "lookup"]);
+        assert_eq!(registry.get("lookup").unwrap().description, "new");
+    }
+
+    #[test]
+    fn agent_steps_carry_registered_tools() {
+        let registry =
+            ToolRegistry::new().register(Tool::webhook("lookup", "Look up", "https://a"));
+        let pipeline = PipelineBuilder::new("support")
+            .llm_workflow("agent")
+            .workflow_type(&LLMWorkflowTypes::Agent)
+            .tools(&registry)
+            .then()
+            .build();
+
+        let spec = LlmSpec::from_data(&pipeline.steps[0].data).unwrap();
+        assert_eq!(pipeline.steps[0].target.as_deref(), Some("agent_executor"));
+        assert_eq!(spec.tools, registry.iter().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn workflow_builders_set_their_target() {
+        let pipeline = PipelineBuilder::new("support")
+            .routing("triage")
+            .then()
+            .orchestrator("refund")
+            .then()
+            .prompt_chain("bug")
+            .then()
+            .evaluator_optimizer("reply")
+            .then()
+            .build();
+
+        let targets: Vec<_> = pipeline.steps.iter().map(|s| s.target.as_deref()).collect();
+        assert_eq!(
+            targets,
+            [
+                Some("routing"),
+                Some("orchestrator"),
+                Some("prompt_chain"),
+                Some("evaluator_optimizer")
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_serialize_in_order_with_a_default() {
+        let pipeline = PipelineBuilder::new("support")
+            .routing("triage")
+            .route("asks for a refund", "refund")
+            .route("reports a bug", "bug")
+            .default_route("bug")
+            .then()
+            .build();
+
+        let data = &pipeline.steps[0].data;
+        assert_eq!(
+            data["routes"],
+            json!([
+                { "condition": "asks for a refund", "target": "refund" },
+                { "condition": "reports a bug", "target": "bug" }
+            ])
+        );
+        assert_eq!(data["default_route"], "bug");
+    }
+
+    #[test]
+    fn workers_chains_and_criteria_serialize() {
+        let pipeline = PipelineBuilder::new("support")
+            .orchestrator("refund")
+            .worker(Worker::new("ledger", "Look up the payment"))
+            .worker(Worker::new("writer", "Draft the reply").model("small"))
+            .then()
+            .prompt_chain("bug")
+            .prompt("Summarize")
+            .chain("List the reproduction steps")
+            .then()
+            .evaluator_optimizer("reply")
+            .criterion("polite")
+            .max_iterations(3)
+            .then()
+            .build();
+
+        assert_eq!(
+            pipeline.steps[0].data["workers"][1],
+            json!({ "name": "writer", "instructions": "Draft the reply", "model": "small" })
+        );
+        let chain = LlmSpec::from_data(&pipeline.steps[1].data).unwrap();
+        assert_eq!(chain.prompts, ["List the reproduction steps"]);
+        let reply = LlmSpec::from_data(&pipeline.steps[2].data).unwrap();
+        assert_eq!(reply.criteria, ["polite"]);
+        assert_eq!(reply.max_iterations, Some(3));
+    }
+}
@@ -0,0 +1,305 @@
+//! Running pipelines locally, without a Helios server.
+//!
+//! [`LocalExecutor`] walks a [`Pipeline`] in dependency order and executes
+//! each step in-process:
+//!
+//! - `script` steps run through the local shell (`sh -c`, or `cmd /C` on
+//!   Windows). Standard output, trimmed, is the step result, parsed as JSON
+//!   when possible. A non-zero exit status fails the step.
+//! - `webhook` steps are sent with reqwest. The response body, parsed as
+//!   JSON when possible, is the step result. A status outside the expected
+//!   ones (any 2xx by default) fails the step. Secrets used for
+//!   authentication are set with [`LocalExecutor::secret`].
+//! - `nimbus_container`, `llm_workflow` and `event` steps need a handler
+//!   registered with [`LocalExecutor::handler`], which may also replace the
+//!   built-in script and webhook behaviour.
+//!
+//! A step whose `when` expression is false is skipped and reports a `null`
+//! result, as do the steps that depend on it. A step whose dependency failed
+//! is not run and reports an error naming that dependency. The result has
+//! the same shape as a server run, and LLM results are checked against their
+//! output schemas in the same way.
+//!
+//! ```no_run
+//! use helios_client::dsl::{ActionType, PipelineBuilder};
+//! use helios_client::local::LocalExecutor;
+//!
+//! # async fn example() -> Result<(), helios_client::HeliosClientError> {
+//! let pipeline = PipelineBuilder::new("local")
+//!     .script("greet", "echo '{\"text\": \"hello\"}'")
+//!     .then()
+//!     .llm_workflow("reply")
+//!     .prompt("Answer {{nested_json 'greet'}}")
+//!     .depends_on("greet")
+//!     .when("greet.text == 'hello'")
+//!     .then()
+//!     .build();
+//!
+//! let run = LocalExecutor::new()
+//!     .handler(ActionType::LlmWorkflow, |_step, _outputs| Ok(serde_json::json!("hi!")))
+//!     .run(&pipeline)
+//!     .await?;
+//! assert!(run.is_success());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::dsl::{ActionType, Pipeline, Step};
+use crate::expression::Expr;
+use crate::run_pipeline::run_pipeline::{RunPipelineRunPipeline, RunPipelineRunPipelineOutputs};
+use crate::webhook::{BodyFormat, WebhookAuth, WebhookSpec};
+use crate::{HeliosClientError, PipelineRun};
+
+/// Results of the steps that completed so far, keyed by step id.
+pub type StepOutputs = HashMap<String, Value>;
+
+/// Executes one step, given the results of the steps that ran before it.
+/// `Err` carries the step error message.
+pub type StepHandler = Box<dyn Fn(&Step, &StepOutputs) -> Result<Value, String> + Send + Sync>;
+
+/// Runs pipelines in-process. See the [module documentation](self).
+#[derive(Default)]
+pub struct LocalExecutor {
+    handlers: HashMap<ActionType, StepHandler>,
+    http_client: reqwest::Client,
+    secrets: HashMap<String, String>,
+}
+
+/// How a step ended, for deciding whether dependents run.
+enum Outcome {
+    Succeeded,
+    Skipped,
+    Failed,
+}
+
+impl LocalExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles every step of `action_type` with `handler`, replacing the
+    /// built-in behaviour for scripts and webhooks.
+    pub fn handler<F>(mut self, action_type: ActionType, handler: F) -> Self
+    where
+        F: Fn(&Step, &StepOutputs) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.handlers.insert(action_type, Box::new(handler));
+        self
+    }
+
+    /// Sets the value of secret `name`, which the server would otherwise
+    /// provide to webhook authentication.
+    pub fn secret(mut self, name: &str, value: &str) -> Self {
+        self.secrets.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Client used for webhook steps.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = client;
+        self
+    }
+
+    /// Runs the pipeline. Steps run one at a time in [`Pipeline::plan`]
+    /// order; a pipeline that fails [`Pipeline::validate`] is rejected with
+    /// [`HeliosClientError::InvalidPipeline`] before any step runs, as
+    /// [`HeliosGraphQLClient::run_pipeline`](crate::HeliosGraphQLClient::run_pipeline)
+    /// would reject it, and its warnings are logged. A `when` expression
+    /// this client cannot parse is rejected with
+    /// [`HeliosClientError::InvalidConfig`], also before any step runs.
+    pub async fn run(&self, pipeline: &Pipeline) -> Result<PipelineRun, HeliosClientError> {
+        crate::check_pipeline(pipeline)?;
+        let plan = pipeline.plan()?;
+        // Parsed up front so an expression this client cannot evaluate stops
+        // the run before any step has side effects.
+        let conditions = pipeline
+            .steps
+            .iter()
+            .filter_map(|s| s.expression.as_ref().map(|e| (s.effective_id(), e)))
+            .map(|(id, expression)| {
+                Expr::parse(expression)
+                    .map(|expr| (id, expr))
+                    .map_err(|e| HeliosClientError::InvalidConfig(format!("step `{}`: {}", id, e)))
+            })
+            .collect::<Result<HashMap<&str, Expr>, _>>()?;
+        let steps: HashMap<&str, &Step> = pipeline
+            .steps
+            .iter()
+            .map(|s| (s.effective_id(), s))
+            .collect();
+
+        let mut outputs = StepOutputs::new();
+        let mut outcomes: HashMap<&str, Outcome> = HashMap::new();
+        let mut errors: HashMap<&str, String> = HashMap::new();
+
+        for id in plan.order() {
+            let step = steps[id];
+            let failed = step
+                .depends_on
+                .iter()
+                .find(|d| matches!(outcomes.get(d.as_str()), Some(Outcome::Failed)));
+            if let Some(dependency) = failed {
+                errors.insert(id, format!("not run: dependency `{}` failed", dependency));
+                outcomes.insert(id, Outcome::Failed);
+                continue;
+            }
+            if step
+                .depends_on
+                .iter()
+                .any(|d| matches!(outcomes.get(d.as_str()), Some(Outcome::Skipped)))
+            {
+                outcomes.insert(id, Outcome::Skipped);
+                continue;
+            }
+
+            if let Some(expr) = conditions.get(id) {
+                if !expr.evaluate(&outputs) {
+                    tracing::debug!(step = id, "condition is false, skipping step");
+                    outcomes.insert(id, Outcome::Skipped);
+                    continue;
+                }
+            }
+
+            tracing::debug!(step = id, "running step locally");
+            match self.execute(step, &outputs).await {
+                Ok(result) => {
+                    outputs.insert(id.to_string(), result);
+                    outcomes.insert(id, Outcome::Succeeded);
+                }
+                Err(error) => {
+                    errors.insert(id, error);
+                    outcomes.insert(id, Outcome::Failed);
+                }
+            }
+        }
+
+        let reported: Vec<&str> = if pipeline.outputs.is_empty() {
+            pipeline.steps.iter().map(|s| s.effective_id()).collect()
+        } else {
+            pipeline.outputs.iter().map(String::as_str).collect()
+        };
+        let success = errors.is_empty();
+        Ok(PipelineRun::new(RunPipelineRunPipeline {
+            success,
+            message: if success {
+                "Pipeline executed successfully".to_string()
+            } else {
+                "Pipeline failed".to_string()
+            },
+            outputs: reported
+                .into_iter()
+                .map(|id| RunPipelineRunPipelineOutputs {
+                    step_id: id.to_string(),
+                    result_json: outputs.get(id).cloned().unwrap_or(Value::Null),
+                    error: errors.remove(id),
+                })
+                .collect(),
+        })
+        .check_output_schemas(&pipeline.output_schemas()))
+    }
+
+    async fn execute(&self, step: &Step, outputs: &StepOutputs) -> Result<Value, String> {
+        if let Some(handler) = self.handlers.get(&step.type_) {
+            return handler(step, outputs);
+        }
+        match step.type_ {
+            ActionType::Script => run_script(step).await,
+            ActionType::Webhook => self.send_webhook(step).await,
+            other => Err(format!("no local handler registered for `{}` steps", other)),
+        }
+    }
+
+    async fn send_webhook(&self, step: &Step) -> Result<Value, String> {
+        let spec = WebhookSpec::from_data(&step.data).map_err(|e| e.to_string())?;
+        let method = reqwest::Method::from_bytes(spec.method.as_str().as_bytes())
+            .expect("HttpMethod names are valid methods");
+
+        let mut request = self.http_client.request(method, &spec.url);
+        for (name, value) in &spec.headers {
+            request = request.header(name, value);
+        }
+        request = match &spec.auth {
+            Some(WebhookAuth::Bearer { token_secret }) => {
+                request.bearer_auth(self.resolve_secret(token_secret)?)
+            }
+            Some(WebhookAuth::Basic {
+                username,
+                password_secret,
+            }) => request.basic_auth(username, Some(self.resolve_secret(password_secret)?)),
+            None => request,
+        };
+        if let Some(seconds) = spec.timeout {
+            request = request.timeout(Duration::from_secs(seconds));
+        }
+        match (
+            spec.body.as_ref().filter(|b| !b.is_null()),
+            spec.body_format,
+        ) {
+            (None, _) => {}
+            (Some(Value::String(raw)), Some(BodyFormat::Form | BodyFormat::Text)) => {
+                request = request.body(raw.clone())
+            }
+            (Some(body), _) => request = request.json(body),
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !spec.accepts_status(status.as_u16()) {
+            return Err(format!("webhook returned {}: {}", status, text));
+        }
+        Ok(parse_output(&text))
+    }
+
+    fn resolve_secret(&self, name: &str) -> Result<&str, String> {
+        self.secrets.get(name).map(String::as_str).ok_or_else(|| {
+            format!(
+                "secret `{}` is not set; register it with `LocalExecutor::secret`",
+                name
+            )
+        })
+    }
+}
+
+async fn run_script(step: &Step) -> Result<Value, String> {
+    let script = step
+        .data
+        .get("script")
+        .and_then(Value::as_str)
+        .ok_or("script step has no `script`")?;
+
+    let mut command = if cfg!(windows) {
+        let mut command = tokio::process::Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = tokio::process::Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let output = command
+        .arg(script)
+        .output()
+        .await
+        .map_err(|e| format!("failed to start shell: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "script exited with {}: {}",
+            output.status,
+            stderr.trim()
+        ));
+    }
+    Ok(parse_output(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// Parses step output as JSON, falling back to a JSON string.
+fn parse_output(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
@@ -0,0 +1,44 @@
+#![allow(clippy::all, warnings)]
+pub struct PipelineUpdates;
+pub mod pipeline_updates {
+    #![allow(dead_code)]
+    use std::result::Result;
+
+    pub const OPERATION_NAME: &str = "PipelineUpdates";
+    pub const QUERY: &str = r#"
+    subscription PipelineUpdates {
+        pipelineUpdates
+    }"#;
+
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = i64;
+    #[allow(dead_code)]
+    type ID = String;
+
+    #[derive(Serialize)]
+    pub struct Variables;
+
+    #[derive(Deserialize, Debug)]
+    pub struct ResponseData {
+        #[serde(rename = "pipelineUpdates")]
+        pub pipeline_updates: String,
+    }
+}
+
+impl graphql_client::GraphQLQuery for PipelineUpdates {
+    type Variables = pipeline_updates::Variables;
+    type ResponseData = pipeline_updates::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: pipeline_updates::QUERY,
+            operation_name: pipeline_updates::OPERATION_NAME,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::dsl::Pipeline;
+use crate::validation::ValidationErrors;
+
+/// How a pipeline will execute, as computed by [`Pipeline::plan`]. Step ids
+/// within each list keep their declaration order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionPlan {
+    /// Steps grouped by when they can start: every step in a stage depends
+    /// only on steps in earlier stages, so a stage can run in parallel.
+    pub stages: Vec<Vec<String>>,
+    /// The longest dependency chain, from a root to a leaf. Its length is a
+    /// lower bound on the number of sequential steps in any run.
+    pub critical_path: Vec<String>,
+    /// Steps without dependencies.
+    pub roots: Vec<String>,
+    /// Steps no other step depends on.
+    pub leaves: Vec<String>,
+    /// Steps that neither are outputs nor feed one, so their results are
+    /// never reported. Always empty when the pipeline declares no outputs,
+    /// since every step is then reported.
+    pub unreachable: Vec<String>,
+}
+
+impl ExecutionPlan {
+    /// All steps in an order that respects their dependencies.
+    pub fn order(&self) -> impl Iterator<Item = &str> {
+        self.stages.iter().flatten().map(String::as_str)
+    }
+
+    /// The stage index of `step_id`, if the step exists.
+    pub fn stage_of(&self, step_id: &str) -> Option<usize> {
+        self.stages
+            .iter()
+            .position(|stage| stage.iter().any(|id| id == step_id))
+    }
+}
+
+impl Pipeline {
+    /// Computes the execution plan. Only the dependency graph is checked
+    /// first (step ids, unknown dependencies and cycles), since those leave
+    /// no valid order; use [`validate`](Self::validate) for the rest.
+    pub fn plan(&self) -> Result<ExecutionPlan, ValidationErrors> {
+        self.validate_graph()?;
+
+        let ids: Vec<&str> = self.steps.iter().map(|s| s.effective_id()).collect();
+        let dependencies: HashMap<&str, &[String]> = self
+            .steps
+            .iter()
+            .map(|s| (s.effective_id(), s.depends_on.as_slice()))
+            .collect();
+
+        // Stage of a step is one past its latest dependency. The graph check rules
+        // out cycles, so repeated passes in declaration order settle.
+        let mut stage: HashMap<&str, usize> = HashMap::new();
+        while stage.len() < ids.len() {
+            for &id in &ids {
+                if stage.contains_key(id) {
+                    continue;
+                }
+                let deps = dependencies[id];
+                if deps.iter().all(|d| stage.contains_key(d.as_str())) {
+                    let level = deps
+                        .iter()
+                        .map(|d| stage[d.as_str()] + 1)
+                        .max()
+                        .unwrap_or(0);
+                    stage.insert(id, level);
+                }
+            }
+        }
+
+        let stage_count = stage.values().max().map_or(0, |max| max + 1);
+        let mut stages = vec![Vec::new(); stage_count];
+        for &id in &ids {
+            stages[stage[id]].push(id.to_string());
+        }
+
+        // The deepest step ends a critical path; walk back through the
+        // dependency one stage earlier each time.
+        let mut critical_path = Vec::new();
+        let mut current = ids.iter().copied().find(|id| stage[id] + 1 == stage_count);
+        while let Some(id) = current {
+            critical_path.push(id.to_string());
+            current = dependencies[id]
+                .iter()
+                .map(String::as_str)
+                .find(|d| stage[d] + 1 == stage[id]);
+        }
+        critical_path.reverse();
+
+        let depended_on: HashSet<&str> = self
+            .steps
+            .iter()
+            .flat_map(|s| s.depends_on.iter().map(String::as_str))
+            .collect();
+
+        let mut consumed: HashSet<&str> = HashSet::new();
+        for output in &self.outputs {
+            consumed.insert(output);
+            consumed.extend(self.upstream_of(output));
+        }
+
+        Ok(ExecutionPlan {
+            stages,
+            critical_path,
+            roots: ids
+                .iter()
+                .filter(|id| dependencies[*id].is_empty())
+                .map(|id| id.to_string())
+                .collect(),
+            leaves: ids
+                .iter()
+                .filter(|id| !depended_on.contains(*id))
+                .map(|id| id.to_string())
+                .collect(),
+            unreachable: ids
+                .iter()
+                .filter(|id| !self.outputs.is_empty() && !consumed.contains(*id))
+                .map(|id| id.to_string())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutionPlan;
+    use crate::dsl::PipelineBuilder;
+    use crate::validation::ValidationErrorKind;
+
+    fn fan_out() -> ExecutionPlan {
+        PipelineBuilder::new("fan-out")
+            .script("fetch", "echo data")
+            .then()
+            .script("config", "echo cfg")
+            .then()
+            .script("parse", "echo parsed")
+            .depends_on("fetch")
+            .then()
+            .script("enrich", "echo enriched")
+            .depends_on_all(&["parse", "config"])
+            .then()
+            .script("audit", "echo audit")
+            .depends_on("fetch")
+            .then()
+            .output("enrich")
+            .build()
+            .plan()
+            .unwrap()
+    }
+
+    #[test]
+    fn groups_steps_into_stages() {
+        let plan = fan_out();
+        assert_eq!(
+            plan.stages,
+            [
+                vec!["fetch", "config"],
+                vec!["parse", "audit"],
+                vec!["enrich"]
+            ]
+        );
+        assert_eq!(
+            plan.order().collect::<Vec<_>>(),
+            ["fetch", "config", "parse", "audit", "enrich"]
+        );
+        assert_eq!(plan.stage_of("enrich"), Some(2));
+        assert_eq!(plan.stage_of("missing"), None);
+    }
+
+    #[test]
+    fn finds_the_critical_path() {
+        assert_eq!(fan_out().critical_path, ["fetch", "parse", "enrich"]);
+    }
+
+    #[test]
+    fn lists_roots_leaves_and_unreachable_steps() {
+        let plan = fan_out();
+        assert_eq!(plan.roots, ["fetch", "config"]);
+        assert_eq!(plan.leaves, ["enrich", "audit"]);
+        assert_eq!(plan.unreachable, ["audit"]);
+    }
+
+    #[test]
+    fn plans_pipelines_with_non_graph_problems() {
+        let pipeline = PipelineBuilder::new("")
+            .webhook("fetch", "not a url")
+            .then()
+            .llm_workflow("summarize")
+            .temperature(9.0)
+            .depends_on("fetch")
+            .then()
+            .output("missing")
+            .build();
+        assert!(pipeline.validate().is_err());
+
+        let plan = pipeline.plan().unwrap();
+
+        assert_eq!(plan.stages, [["fetch"], ["summarize"]]);
+    }
+
+    #[test]
+    fn rejects_graphs_without_an_order() {
+        let pipeline = PipelineBuilder::new("p")
+            .script("a", "echo a")
+            .depends_on("b")
+            .then()
+            .script("b", "echo b")
+            .depends_on("a")
+            .then()
+            .script("b", "echo again")
+            .depends_on("missing")
+            .then()
+            .build();
+
+        let errors = pipeline.plan().unwrap_err();
+
+        let kinds: Vec<_> = errors.iter().map(|e| &e.kind).collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                ValidationErrorKind::DuplicateStepId(_),
+                ValidationErrorKind::UnknownDependency(_),
+                ValidationErrorKind::DependencyCycle(_),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+/// Header carrying the idempotency key on `runPipeline` and
+/// `createPipeline` requests.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// HTTP statuses that indicate a transient failure: 408, 429, 502, 503 and
+/// 504. The default for [`RetryPolicy::retryable_statuses`] and the statuses
+/// [`HeliosClientError::is_retryable`](crate::HeliosClientError::is_retryable)
+/// accepts.
+pub const TRANSIENT_STATUSES: &[StatusCode] = &[
+    StatusCode::REQUEST_TIMEOUT,
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Opt-in policy for retrying transient failures.
+///
+/// Only transport failures and HTTP statuses are retried; GraphQL errors in a
+/// successful response never are. Delays grow exponentially from
+/// `initial_backoff` up to `max_backoff`, with full jitter when enabled. A
+/// `Retry-After` header on a retryable response overrides the computed delay
+/// (still capped at `max_backoff`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+    pub retryable_statuses: Vec<StatusCode>,
+    pub retry_connect_errors: bool,
+    pub retry_timeouts: bool,
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: true,
+            retryable_statuses: TRANSIENT_STATUSES.to_vec(),
+            retry_connect_errors: true,
+            retry_timeouts: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retryable_statuses(mut self, statuses: &[StatusCode]) -> Self {
+        self.retryable_statuses = statuses.to_vec();
+        self
+    }
+
+    pub fn retry_connect_errors(mut self, retry: bool) -> Self {
+        self.retry_connect_errors = retry;
+        self
+    }
+
+    pub fn retry_timeouts(mut self, retry: bool) -> Self {
+        self.retry_timeouts = retry;
+        self
+    }
+
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    pub(crate) fn should_retry_status(&self, status: StatusCode) -> bool {
+        self.retryable_statuses.contains(&status)
+    }
+
+    pub(crate) fn should_retry_error(&self, error: &reqwest::Error) -> bool {
+        (self.retry_connect_errors && error.is_connect())
+            || (self.retry_timeouts && error.is_timeout())
+    }
+
+    /// Delay before the attempt following `attempt` (1-based).
+    pub(crate) fn backoff(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if self.respect_retry_after {
+            if let Some(delay) = headers.and_then(retry_after) {
+                return delay.min(self.max_backoff);
+            }
+        }
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let seconds = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let delay = Duration::from_secs_f64(seconds.min(self.max_backoff.as_secs_f64()));
+        if self.jitter {
+            delay.mul_f64(fastrand::f64())
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parses `Retry-After` as either delay-seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Derives a stable idempotency key from a serialized request body, so the
+/// same pipeline definition always maps to the same key.
+pub(crate) fn idempotency_key(body: &[u8]) -> String {
+    uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, body).to_string()
+}
+
+/// A fresh idempotency key, for requests such as `runPipeline` where
+/// repeating the same body is meant to have an effect again.
+pub(crate) fn unique_idempotency_key() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
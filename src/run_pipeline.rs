@@ -31,7 +31,7 @@ pub mod run_pipeline {
     type ID = String;
     type JSON = super::JSON;
 
-    #[derive()]
+    #[derive(Debug)]
     pub enum ActionType {
         ACTION_TYPE_UNSPECIFIED,
         WEBHOOK,
@@ -89,6 +89,8 @@ pub mod run_pipeline {
         pub target: Option<String>,
         #[serde(rename = "dependsOn")]
         pub depends_on: Option<String>,
+        #[serde(rename = "dependsOnAll", skip_serializing_if = "Option::is_none")]
+        pub depends_on_all: Option<Vec<String>>,
         pub expression: Option<String>,
     }
 
@@ -99,20 +101,20 @@ pub mod run_pipeline {
 
     impl Variables {}
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct ResponseData {
         #[serde(rename = "runPipeline")]
         pub run_pipeline: RunPipelineRunPipeline,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct RunPipelineRunPipeline {
         pub success: Boolean,
         pub message: String,
         pub outputs: Vec<RunPipelineRunPipelineOutputs>,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Debug)]
     pub struct RunPipelineRunPipelineOutputs {
         #[serde(rename = "stepId")]
         pub step_id: String,
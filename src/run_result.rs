@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::run_pipeline::run_pipeline::{RunPipelineRunPipeline, RunPipelineRunPipelineOutputs};
+use crate::schema::{self, SchemaViolation};
+use crate::HeliosClientError;
+
+/// A step that reported an error in a pipeline run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepError {
+    pub step_id: String,
+    pub error: String,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.step_id, self.error)
+    }
+}
+
+/// The result of [`HeliosGraphQLClient::run_pipeline`](crate::HeliosGraphQLClient::run_pipeline),
+/// with lookups by step id.
+///
+/// Derefs to the raw [`RunPipelineRunPipeline`] response, so `success`,
+/// `message` and `outputs` remain directly accessible.
+///
+/// Results that do not match their step's output schema (see
+/// [`crate::schema`]) count as step errors.
+#[derive(Debug)]
+pub struct PipelineRun {
+    inner: RunPipelineRunPipeline,
+    schema_violations: HashMap<String, Vec<SchemaViolation>>,
+}
+
+impl PipelineRun {
+    pub fn new(inner: RunPipelineRunPipeline) -> Self {
+        Self {
+            inner,
+            schema_violations: HashMap::new(),
+        }
+    }
+
+    /// Checks each step result against `schemas`, keyed by step id, as
+    /// returned by [`Pipeline::output_schemas`](crate::dsl::Pipeline::output_schemas).
+    /// Steps that reported an error or a `null` result are not checked.
+    pub fn check_output_schemas(mut self, schemas: &HashMap<String, Value>) -> Self {
+        for output in &self.inner.outputs {
+            let Some(schema) = schemas.get(&output.step_id) else {
+                continue;
+            };
+            if output.error.is_some() || output.result_json.is_null() {
+                continue;
+            }
+            if let Err(violations) = schema::validate(schema, &output.result_json) {
+                tracing::warn!(
+                    step = %output.step_id,
+                    "step result does not match its output schema"
+                );
+                self.schema_violations
+                    .insert(output.step_id.clone(), violations);
+            }
+        }
+        self
+    }
+
+    /// The output reported for `step_id`, if the server returned one.
+    pub fn output(&self, step_id: &str) -> Option<&RunPipelineRunPipelineOutputs> {
+        self.inner.outputs.iter().find(|o| o.step_id == step_id)
+    }
+
+    /// Deserializes the `result_json` of `step_id` into `T`, failing with
+    /// [`HeliosClientError::OutputSchema`] if it did not match the step's
+    /// output schema.
+    pub fn output_as<T: DeserializeOwned>(&self, step_id: &str) -> Result<T, HeliosClientError> {
+        let output = self
+            .output(step_id)
+            .ok_or_else(|| HeliosClientError::MissingOutput(step_id.to_string()))?;
+        if let Some(violations) = self.schema_violations.get(step_id) {
+            return Err(HeliosClientError::OutputSchema {
+                step_id: step_id.to_string(),
+                violations: violations.clone(),
+            });
+        }
+        Ok(T::deserialize(&output.result_json)?)
+    }
+
+    /// How the result of `step_id` breaks its output schema, if it does.
+    pub fn schema_violations(&self, step_id: &str) -> Option<&[SchemaViolation]> {
+        self.schema_violations.get(step_id).map(Vec::as_slice)
+    }
+
+    /// Ids of the steps that reported an error or broke their output schema.
+    pub fn failed_steps(&self) -> impl Iterator<Item = &str> {
+        self.inner
+            .outputs
+            .iter()
+            .filter(|o| o.error.is_some() || self.schema_violations.contains_key(&o.step_id))
+            .map(|o| o.step_id.as_str())
+    }
+
+    /// The per-step errors, in output order.
+    pub fn errors(&self) -> Vec<StepError> {
+        self.inner
+            .outputs
+            .iter()
+            .filter_map(|o| {
+                let error = match (&o.error, self.schema_violations.get(&o.step_id)) {
+                    (Some(error), _) => error.clone(),
+                    (None, Some(violations)) => format!(
+                        "result does not match output schema: {}",
+                        schema::describe(violations)
+                    ),
+                    (None, None) => return None,
+                };
+                Some(StepError {
+                    step_id: o.step_id.clone(),
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the run succeeded and no step reported an error or broke its
+    /// output schema.
+    pub fn is_success(&self) -> bool {
+        self.inner.success
+            && self.schema_violations.is_empty()
+            && self.inner.outputs.iter().all(|o| o.error.is_none())
+    }
+
+    /// Converts a failed run (`success == false` or any step error) into
+    /// [`HeliosClientError::PipelineFailed`].
+    pub fn into_result(self) -> Result<Self, HeliosClientError> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(HeliosClientError::PipelineFailed {
+                step_errors: self.errors(),
+                message: self.inner.message,
+            })
+        }
+    }
+
+    pub fn into_inner(self) -> RunPipelineRunPipeline {
+        self.inner
+    }
+}
+
+impl Deref for PipelineRun {
+    type Target = RunPipelineRunPipeline;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl From<RunPipelineRunPipeline> for PipelineRun {
+    fn from(inner: RunPipelineRunPipeline) -> Self {
+        Self::new(inner)
+    }
+}
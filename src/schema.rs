@@ -0,0 +1,378 @@
+//! Output schemas for LLM steps.
+//!
+//! An LLM step can declare a JSON Schema for its result with
+//! [`output_schema_value`](crate::dsl::LlmWorkflowStepBuilder::output_schema_value),
+//! or derive one from a Rust type with `output_schema::<T>()` when the
+//! `schema` feature is enabled.
+//! The schema is sent in the step's `data.output_schema`, and when a run
+//! completes each result is checked against it with [`validate`].
+//!
+//! The checker covers the keywords that describe data shape: `type`, `enum`,
+//! `const`, `properties`, `required`, `additionalProperties`, `items`,
+//! `prefixItems`, `minItems`, `maxItems`, `minLength`, `maxLength`,
+//! `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `allOf`,
+//! `anyOf`, `oneOf`, `not` and local `$ref`s (`#`, `#/$defs/..`,
+//! `#/definitions/..`). Other keywords, such as `pattern` and `format`, are
+//! accepted but not checked.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::dsl::{ActionType, Pipeline};
+
+/// One way in which a value does not match its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value; empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Checks `instance` against `schema`, returning every violation found.
+pub fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+    let mut checker = Checker {
+        root: schema,
+        resolving: Vec::new(),
+        violations: Vec::new(),
+    };
+    checker.check(schema, instance, "");
+    if checker.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.violations)
+    }
+}
+
+/// Joins violations into one line, for error messages.
+pub(crate) fn describe(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl Pipeline {
+    /// The declared output schemas of LLM steps, keyed by step id.
+    pub fn output_schemas(&self) -> HashMap<String, Value> {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step.type_, ActionType::LlmWorkflow))
+            .filter_map(|step| {
+                let schema = step.data.get("output_schema")?;
+                Some((step.effective_id().to_string(), schema.clone()))
+            })
+            .collect()
+    }
+}
+
+struct Checker<'a> {
+    root: &'a Value,
+    /// `$ref`s being resolved, with the instance path each was resolved at.
+    /// A `$ref` reached again at the same path adds no constraint, and
+    /// following it would recurse forever.
+    resolving: Vec<(&'a str, String)>,
+    violations: Vec<SchemaViolation>,
+}
+
+impl<'a> Checker<'a> {
+    fn fail(&mut self, path: &str, message: String) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    /// Whether `instance` matches `schema`, without recording violations.
+    fn matches(&self, schema: &'a Value, instance: &Value) -> bool {
+        let mut probe = Checker {
+            root: self.root,
+            resolving: self.resolving.clone(),
+            violations: Vec::new(),
+        };
+        probe.check(schema, instance, "");
+        probe.violations.is_empty()
+    }
+
+    fn check(&mut self, schema: &'a Value, instance: &Value, path: &str) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return self.fail(path, "no value is allowed here".to_string()),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let entry = (reference, path.to_string());
+            if !self.resolving.contains(&entry) {
+                match self.resolve(reference) {
+                    Some(target) => {
+                        self.resolving.push(entry);
+                        self.check(target, instance, path);
+                        self.resolving.pop();
+                    }
+                    None => self.fail(path, format!("unresolvable `$ref` `{}`", reference)),
+                }
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let allowed: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|t| has_type(instance, t)) {
+                self.fail(
+                    path,
+                    format!(
+                        "expected {}, found {}",
+                        allowed.join(" or "),
+                        type_name(instance)
+                    ),
+                );
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(instance) {
+                self.fail(
+                    path,
+                    format!("{} is not one of the allowed values", instance),
+                );
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != instance {
+                self.fail(path, format!("expected {}, found {}", expected, instance));
+            }
+        }
+
+        match instance {
+            Value::Object(object) => self.check_object(schema, object, path),
+            Value::Array(items) => self.check_array(schema, items, path),
+            Value::String(s) => self.check_string(schema, s, path),
+            Value::Number(n) => {
+                if let Some(n) = n.as_f64() {
+                    self.check_number(schema, n, path)
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.check(sub, instance, path);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|sub| self.matches(sub, instance)) {
+                self.fail(path, "matches none of the `anyOf` schemas".to_string());
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let count = one.iter().filter(|sub| self.matches(sub, instance)).count();
+            if count != 1 {
+                self.fail(
+                    path,
+                    format!("matches {} of the `oneOf` schemas instead of one", count),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, instance) {
+                self.fail(path, "matches the `not` schema".to_string());
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+    ) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    self.fail(path, format!("missing required property `{}`", name));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, value) in object {
+            let child = format!("{}/{}", path, escape_pointer(name));
+            match properties.and_then(|p| p.get(name)) {
+                Some(property) => self.check(property, value, &child),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.fail(path, format!("unexpected property `{}`", name))
+                    }
+                    Some(additional) => self.check(additional, value, &child),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &'a Map<String, Value>, items: &[Value], path: &str) {
+        let prefix = schema
+            .get("prefixItems")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        for (index, item) in items.iter().enumerate() {
+            let child = format!("{}/{}", path, index);
+            if let Some(item_schema) = prefix.get(index) {
+                self.check(item_schema, item, &child);
+            } else if let Some(item_schema) = schema.get("items") {
+                self.check(item_schema, item, &child);
+            }
+        }
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                self.fail(
+                    path,
+                    format!("expected at least {} items, found {}", min, items.len()),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (items.len() as u64) > max {
+                self.fail(
+                    path,
+                    format!("expected at most {} items, found {}", max, items.len()),
+                );
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &Map<String, Value>, s: &str, path: &str) {
+        let length = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.fail(
+                    path,
+                    format!("expected at least {} characters, found {}", min, length),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.fail(
+                    path,
+                    format!("expected at most {} characters, found {}", max, length),
+                );
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &Map<String, Value>, n: f64, path: &str) {
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum").filter(|min| n < *min) {
+            self.fail(path, format!("{} is less than the minimum {}", n, min));
+        }
+        if let Some(max) = bound("maximum").filter(|max| n > *max) {
+            self.fail(path, format!("{} is greater than the maximum {}", n, max));
+        }
+        if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+            self.fail(path, format!("{} is not greater than {}", n, min));
+        }
+        if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+            self.fail(path, format!("{} is not less than {}", n, max));
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn recursive_refs_check_nested_values() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "children": { "type": "array", "items": { "$ref": "#" } }
+            },
+            "required": ["children"]
+        });
+
+        assert!(validate(&schema, &json!({ "children": [{ "children": [] }] })).is_ok());
+        let violations = validate(&schema, &json!({ "children": [{}] })).unwrap_err();
+        assert_eq!(violations[0].path, "/children/0");
+    }
+
+    #[test]
+    fn self_referential_refs_do_not_recurse_forever() {
+        for schema in [
+            json!({ "$ref": "#" }),
+            json!({ "anyOf": [{ "$ref": "#" }] }),
+            json!({ "$ref": "#/$defs/a", "$defs": { "a": { "$ref": "#/$defs/a" } } }),
+            json!({
+                "$ref": "#/$defs/a",
+                "$defs": {
+                    "a": { "$ref": "#/$defs/b" },
+                    "b": { "allOf": [{ "$ref": "#/$defs/a" }, { "type": "string" }] }
+                }
+            }),
+        ] {
+            let _ = validate(&schema, &json!("value"));
+        }
+
+        let schema = json!({
+            "$ref": "#/$defs/a",
+            "$defs": { "a": { "allOf": [{ "$ref": "#/$defs/a" }, { "type": "string" }] } }
+        });
+        assert!(validate(&schema, &json!("value")).is_ok());
+        assert!(validate(&schema, &json!(1)).is_err());
+    }
+}
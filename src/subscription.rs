@@ -0,0 +1,499 @@
+//! GraphQL subscriptions over WebSocket.
+//!
+//! Both the `graphql-transport-ws` protocol and the legacy `graphql-ws`
+//! (subscriptions-transport-ws) protocol are offered during the handshake;
+//! whichever the server selects is spoken for the rest of the connection.
+
+use std::pin::Pin;
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use graphql_client::GraphQLQuery;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::pipeline_updates::{pipeline_updates, PipelineUpdates};
+use crate::{HeliosClientError, HeliosGraphQLClient};
+
+/// Stream of live updates returned by
+/// [`HeliosGraphQLClient::subscribe_pipeline_updates`].
+pub type PipelineUpdateStream =
+    Pin<Box<dyn Stream<Item = Result<PipelineUpdate, HeliosClientError>> + Send>>;
+
+/// WebSocket sub-protocol used for a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsProtocol {
+    /// The `graphql-transport-ws` protocol (graphql-ws library).
+    GraphqlTransportWs,
+    /// The legacy `graphql-ws` protocol (subscriptions-transport-ws library).
+    GraphqlWs,
+}
+
+impl WsProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlTransportWs => "graphql-transport-ws",
+            WsProtocol::GraphqlWs => "graphql-ws",
+        }
+    }
+
+    /// The protocol the server selected in its `Sec-WebSocket-Protocol`
+    /// response header. Servers that omit the header are assumed to speak
+    /// `graphql-transport-ws`.
+    fn negotiated(header: Option<&str>) -> Self {
+        match header.map(str::trim) {
+            Some("graphql-ws") => WsProtocol::GraphqlWs,
+            _ => WsProtocol::GraphqlTransportWs,
+        }
+    }
+
+    fn subscribe_type(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlTransportWs => "subscribe",
+            WsProtocol::GraphqlWs => "start",
+        }
+    }
+}
+
+/// A single progress event published by `pipelineUpdates`.
+///
+/// The server sends each update as a JSON-encoded string. Payloads that are
+/// not a recognised event are surfaced as [`PipelineUpdate::Other`] with the
+/// raw string, so newer servers never break older clients.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineUpdate {
+    StepStarted {
+        pipeline: Option<String>,
+        step_id: String,
+    },
+    StepFinished {
+        pipeline: Option<String>,
+        step_id: String,
+        result_json: Option<Value>,
+        error: Option<String>,
+    },
+    Log {
+        pipeline: Option<String>,
+        step_id: Option<String>,
+        message: String,
+    },
+    Other(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RawUpdate {
+    #[serde(alias = "stepStarted", alias = "STEP_STARTED")]
+    StepStarted {
+        #[serde(default)]
+        pipeline: Option<String>,
+        #[serde(alias = "stepId")]
+        step_id: String,
+    },
+    #[serde(alias = "stepFinished", alias = "STEP_FINISHED")]
+    StepFinished {
+        #[serde(default)]
+        pipeline: Option<String>,
+        #[serde(alias = "stepId")]
+        step_id: String,
+        #[serde(default, alias = "resultJson")]
+        result_json: Option<Value>,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    #[serde(alias = "LOG")]
+    Log {
+        #[serde(default)]
+        pipeline: Option<String>,
+        #[serde(default, alias = "stepId")]
+        step_id: Option<String>,
+        message: String,
+    },
+}
+
+impl PipelineUpdate {
+    /// Parses the string payload of a `pipelineUpdates` event.
+    pub fn parse(raw: &str) -> Self {
+        match serde_json::from_str::<RawUpdate>(raw) {
+            Ok(RawUpdate::StepStarted { pipeline, step_id }) => {
+                PipelineUpdate::StepStarted { pipeline, step_id }
+            }
+            Ok(RawUpdate::StepFinished {
+                pipeline,
+                step_id,
+                result_json,
+                error,
+            }) => PipelineUpdate::StepFinished {
+                pipeline,
+                step_id,
+                result_json,
+                error,
+            },
+            Ok(RawUpdate::Log {
+                pipeline,
+                step_id,
+                message,
+            }) => PipelineUpdate::Log {
+                pipeline,
+                step_id,
+                message,
+            },
+            Err(_) => PipelineUpdate::Other(raw.to_string()),
+        }
+    }
+}
+
+const SUBSCRIPTION_ID: &str = "1";
+
+impl HeliosGraphQLClient {
+    /// Opens a WebSocket to the GraphQL endpoint and subscribes to
+    /// `pipelineUpdates`.
+    ///
+    /// The connection handshake completes before this returns, so
+    /// connection failures surface here rather than on the first poll of the
+    /// stream. The stream ends when the server completes the subscription or
+    /// closes the socket.
+    pub async fn subscribe_pipeline_updates(
+        &self,
+    ) -> Result<PipelineUpdateStream, HeliosClientError> {
+        let mut request = websocket_url(&self.endpoint).into_client_request()?;
+        request.headers_mut().extend(self.headers.clone());
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static("graphql-transport-ws, graphql-ws"),
+        );
+
+        let connect = tokio_tungstenite::connect_async(request);
+        let (mut socket, response) = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                HeliosClientError::Subscription("timed out connecting".to_string())
+            })??,
+            None => connect.await?,
+        };
+        let protocol = WsProtocol::negotiated(
+            response
+                .headers()
+                .get(SEC_WEBSOCKET_PROTOCOL)
+                .and_then(|v| v.to_str().ok()),
+        );
+        tracing::debug!(protocol = protocol.as_str(), "subscription connected");
+
+        send_json(
+            &mut socket,
+            json!({ "type": "connection_init", "payload": self.connection_params() }),
+        )
+        .await?;
+        loop {
+            let message = socket.next().await.ok_or_else(|| {
+                HeliosClientError::Subscription("connection closed before ack".to_string())
+            })??;
+            let Some(frame) = parse_frame(&message)? else {
+                continue;
+            };
+            match frame.type_.as_str() {
+                "connection_ack" => break,
+                "ka" => continue,
+                "ping" => send_json(&mut socket, json!({ "type": "pong" })).await?,
+                "connection_error" => {
+                    return Err(HeliosClientError::Subscription(format!(
+                        "connection rejected: {}",
+                        frame.payload.unwrap_or(Value::Null)
+                    )))
+                }
+                other => {
+                    return Err(HeliosClientError::Subscription(format!(
+                        "unexpected `{}` message before connection_ack",
+                        other
+                    )))
+                }
+            }
+        }
+
+        let query = PipelineUpdates::build_query(pipeline_updates::Variables);
+        send_json(
+            &mut socket,
+            json!({
+                "id": SUBSCRIPTION_ID,
+                "type": protocol.subscribe_type(),
+                "payload": query,
+            }),
+        )
+        .await?;
+
+        let stream = async_stream::try_stream! {
+            while let Some(message) = socket.next().await {
+                let message = message?;
+                if message.is_close() {
+                    break;
+                }
+                let Some(frame) = parse_frame(&message)? else {
+                    continue;
+                };
+                match frame.type_.as_str() {
+                    "next" | "data" => {
+                        let payload: graphql_client::Response<pipeline_updates::ResponseData> =
+                            serde_json::from_value(frame.payload.unwrap_or(Value::Null))?;
+                        if let Some(errors) = payload.errors {
+                            Err(HeliosClientError::GraphQL(errors.into()))?;
+                        }
+                        let data = payload.data.ok_or(HeliosClientError::NoData)?;
+                        yield PipelineUpdate::parse(&data.pipeline_updates);
+                    }
+                    "ping" => send_json(&mut socket, json!({ "type": "pong" })).await?,
+                    "ka" | "pong" => {}
+                    "error" => {
+                        let errors = match frame.payload.unwrap_or(Value::Null) {
+                            Value::Array(errors) => errors,
+                            other => vec![other],
+                        };
+                        let errors = errors
+                            .into_iter()
+                            .map(serde_json::from_value)
+                            .collect::<Result<Vec<graphql_client::Error>, _>>()?;
+                        Err(HeliosClientError::GraphQL(errors.into()))?;
+                    }
+                    "complete" => break,
+                    other => tracing::debug!(message_type = other, "ignoring subscription message"),
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+impl HeliosGraphQLClient {
+    /// Credentials are repeated in the `connection_init` payload because many
+    /// servers authenticate subscriptions there rather than on the handshake.
+    fn connection_params(&self) -> Value {
+        let params: serde_json::Map<String, Value> = self
+            .headers
+            .iter()
+            .filter(|(_, value)| value.is_sensitive())
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.to_string(), Value::String(value.to_string())))
+            })
+            .collect();
+        Value::Object(params)
+    }
+}
+
+#[derive(Deserialize)]
+struct Frame {
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(default)]
+    payload: Option<Value>,
+}
+
+fn parse_frame(message: &Message) -> Result<Option<Frame>, HeliosClientError> {
+    match message {
+        Message::Text(text) => Ok(Some(serde_json::from_str(text.as_str())?)),
+        Message::Binary(bytes) => Ok(Some(serde_json::from_slice(bytes)?)),
+        _ => Ok(None),
+    }
+}
+
+async fn send_json<S>(socket: &mut S, value: Value) -> Result<(), HeliosClientError>
+where
+    S: futures_util::Sink<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    socket.send(Message::text(value.to_string())).await?;
+    Ok(())
+}
+
+/// Maps an `http(s)://` GraphQL endpoint to its `ws(s)://` counterpart.
+fn websocket_url(endpoint: &str) -> String {
+    if let Some(rest) = endpoint.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = endpoint.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        endpoint.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeliosClientBuilder;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+    #[test]
+    fn selects_protocol_from_response_header() {
+        assert_eq!(
+            WsProtocol::negotiated(Some("graphql-ws")),
+            WsProtocol::GraphqlWs
+        );
+        assert_eq!(
+            WsProtocol::negotiated(Some("graphql-transport-ws")),
+            WsProtocol::GraphqlTransportWs
+        );
+        assert_eq!(WsProtocol::negotiated(None), WsProtocol::GraphqlTransportWs);
+        assert_eq!(WsProtocol::GraphqlWs.subscribe_type(), "start");
+        assert_eq!(WsProtocol::GraphqlTransportWs.subscribe_type(), "subscribe");
+    }
+
+    #[test]
+    fn parses_snake_and_camel_case_updates() {
+        assert_eq!(
+            PipelineUpdate::parse(r#"{"type": "step_started", "step_id": "fetch"}"#),
+            PipelineUpdate::StepStarted {
+                pipeline: None,
+                step_id: "fetch".to_string()
+            }
+        );
+        assert_eq!(
+            PipelineUpdate::parse(
+                r#"{"type": "stepFinished", "pipeline": "p", "stepId": "fetch", "resultJson": {"n": 1}}"#
+            ),
+            PipelineUpdate::StepFinished {
+                pipeline: Some("p".to_string()),
+                step_id: "fetch".to_string(),
+                result_json: Some(json!({ "n": 1 })),
+                error: None,
+            }
+        );
+        assert_eq!(
+            PipelineUpdate::parse(r#"{"type": "LOG", "message": "hi"}"#),
+            PipelineUpdate::Log {
+                pipeline: None,
+                step_id: None,
+                message: "hi".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_updates_keep_the_raw_payload() {
+        for raw in [r#"{"type": "pipeline_paused"}"#, "not json"] {
+            assert_eq!(
+                PipelineUpdate::parse(raw),
+                PipelineUpdate::Other(raw.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn parses_text_and_binary_frames_and_skips_control_frames() {
+        let text = parse_frame(&Message::text(r#"{"type": "ka"}"#)).unwrap();
+        assert_eq!(text.unwrap().type_, "ka");
+        let binary = parse_frame(&Message::binary(
+            br#"{"type": "next", "payload": 1}"#.to_vec(),
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(binary.payload, Some(json!(1)));
+        assert!(parse_frame(&Message::Ping(Vec::new().into()))
+            .unwrap()
+            .is_none());
+        assert!(parse_frame(&Message::text("{")).is_err());
+    }
+
+    #[test]
+    fn maps_http_endpoints_to_websocket_urls() {
+        assert_eq!(websocket_url("https://h/graphql"), "wss://h/graphql");
+        assert_eq!(websocket_url("http://h/graphql"), "ws://h/graphql");
+        assert_eq!(websocket_url("ws://h/graphql"), "ws://h/graphql");
+    }
+
+    #[test]
+    fn connection_params_carry_only_credentials() {
+        let client = HeliosClientBuilder::new("http://h/graphql")
+            .bearer_token("t")
+            .header("x-team", "ops")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.connection_params(),
+            json!({ "authorization": "Bearer t" })
+        );
+    }
+
+    /// Accepts one connection selecting `protocol`, acknowledges it, checks
+    /// the subscribe message and sends one update followed by `complete`.
+    // The handshake callback's error type is fixed by tungstenite.
+    #[allow(clippy::result_large_err)]
+    async fn serve_once(protocol: WsProtocol) -> String {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let endpoint = format!("http://{}/graphql", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let select = |request: &Request, mut response: Response| {
+                let offered = request.headers()[SEC_WEBSOCKET_PROTOCOL].to_str().unwrap();
+                assert!(offered.contains(protocol.as_str()));
+                response.headers_mut().insert(
+                    SEC_WEBSOCKET_PROTOCOL,
+                    HeaderValue::from_static(protocol.as_str()),
+                );
+                Ok(response)
+            };
+            let mut socket = tokio_tungstenite::accept_hdr_async(stream, select)
+                .await
+                .unwrap();
+
+            let recv = |message: Message| -> Value {
+                serde_json::from_str(message.to_text().unwrap()).unwrap()
+            };
+            let init = recv(socket.next().await.unwrap().unwrap());
+            assert_eq!(init["type"], "connection_init");
+            send_json(&mut socket, json!({ "type": "connection_ack" }))
+                .await
+                .unwrap();
+
+            let subscribe = recv(socket.next().await.unwrap().unwrap());
+            assert_eq!(subscribe["type"], protocol.subscribe_type());
+            assert_eq!(subscribe["payload"]["operationName"], "PipelineUpdates");
+            let data_type = match protocol {
+                WsProtocol::GraphqlTransportWs => "next",
+                WsProtocol::GraphqlWs => "data",
+            };
+            let update = json!({ "type": "step_started", "step_id": "fetch" }).to_string();
+            send_json(
+                &mut socket,
+                json!({
+                    "id": SUBSCRIPTION_ID,
+                    "type": data_type,
+                    "payload": { "data": { "pipelineUpdates": update } },
+                }),
+            )
+            .await
+            .unwrap();
+            send_json(
+                &mut socket,
+                json!({ "id": SUBSCRIPTION_ID, "type": "complete" }),
+            )
+            .await
+            .unwrap();
+        });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn subscribes_with_either_protocol() {
+        for protocol in [WsProtocol::GraphqlTransportWs, WsProtocol::GraphqlWs] {
+            let endpoint = serve_once(protocol).await;
+            let client = HeliosGraphQLClient::new(endpoint);
+            let updates: Vec<_> = client
+                .subscribe_pipeline_updates()
+                .await
+                .unwrap()
+                .collect()
+                .await;
+            assert_eq!(updates.len(), 1, "{:?}", protocol);
+            assert_eq!(
+                updates[0].as_ref().unwrap(),
+                &PipelineUpdate::StepStarted {
+                    pipeline: None,
+                    step_id: "fetch".to_string()
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Prompt templates.
+//!
+//! LLM prompts pull in upstream outputs with Handlebars-style helper tags
+//! such as `{{nested_json 'current_weather'}}`. Other tags, including bare
+//! variables like `{{ user_name }}` or `{{ input.city }}`, are template
+//! variables rather than step references and are ignored.
+//!
+//! ```
+//! use helios_client::template::step_refs;
+//!
+//! let refs = step_refs("Weather: {{nested_json 'weather'}}, city: {{ input.city }}").unwrap();
+//! assert_eq!(refs, ["weather"]);
+//! ```
+
+use thiserror::Error;
+
+/// Helpers whose first quoted argument is a step id.
+const STEP_HELPERS: &[&str] = &["nested_json"];
+
+/// A malformed tag in a prompt template.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at offset {position}")]
+pub struct TemplateError {
+    /// Byte offset into the template.
+    pub position: usize,
+    pub message: String,
+}
+
+/// Ids of the steps a template reads, without duplicates, in order of first
+/// appearance.
+pub fn step_refs(template: &str) -> Result<Vec<&str>, TemplateError> {
+    let mut refs: Vec<&str> = Vec::new();
+    let mut rest = template;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find("{{") {
+        let position = offset + start;
+        let inner_start = start + 2;
+        let Some(len) = rest[inner_start..].find("}}") else {
+            return Err(TemplateError {
+                position,
+                message: "unterminated `{{`".to_string(),
+            });
+        };
+        let tag = &rest[inner_start..inner_start + len];
+        if let Some(step) =
+            tag_step_ref(tag).map_err(|message| TemplateError { position, message })?
+        {
+            if !refs.contains(&step) {
+                refs.push(step);
+            }
+        }
+        let consumed = inner_start + len + 2;
+        rest = &rest[consumed..];
+        offset += consumed;
+    }
+    Ok(refs)
+}
+
+/// The step read by the contents of one `{{ .. }}` tag, if any.
+fn tag_step_ref(tag: &str) -> Result<Option<&str>, String> {
+    let tag = tag
+        .trim_start_matches(['{', '~'])
+        .trim_end_matches(['}', '~'])
+        .trim();
+    if tag.is_empty() {
+        return Err("empty tag".to_string());
+    }
+    let (head, args) = match tag.split_once(char::is_whitespace) {
+        Some((head, args)) => (head, args.trim()),
+        None => (tag, ""),
+    };
+    if !STEP_HELPERS.contains(&head) {
+        return Ok(None);
+    }
+    let Some(quote) = args.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+        return Err(format!("`{}` expects a quoted step id", head));
+    };
+    let Some(end) = args[1..].find(quote) else {
+        return Err(format!("unterminated step id in `{}`", head));
+    };
+    let step = &args[1..1 + end];
+    if step.is_empty() {
+        return Err(format!("`{}` expects a quoted step id", head));
+    }
+    Ok(Some(step))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helpers_reference_steps() {
+        assert_eq!(
+            step_refs("{{nested_json 'a'}} {{~ nested_json \"b\" ~}} {{{nested_json 'a'}}}")
+                .unwrap(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn plain_variables_are_not_step_references() {
+        let template = "Hi {{ user_name }} in {{ input.city }} ({{ items[0] }}): \
+                        {{#if flag}}{{this}}{{else}}{{/if}} {{! note }} {{> partial}}";
+        assert_eq!(step_refs(template).unwrap(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn malformed_helper_tags_are_errors() {
+        assert_eq!(step_refs("x {{nested_json 'a'").unwrap_err().position, 2);
+        for template in [
+            "{{nested_json}}",
+            "{{nested_json a}}",
+            "{{nested_json ''}}",
+            "{{nested_json 'a}}",
+            "{{ }}",
+        ] {
+            assert!(step_refs(template).is_err(), "{}", template);
+        }
+    }
+}
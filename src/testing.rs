@@ -0,0 +1,341 @@
+//! An in-process stand-in for a Helios server, for tests.
+//!
+//! [`MockHeliosServer`] listens on an ephemeral localhost port and answers
+//! `runPipeline`, `createPipeline` and `hello` without any network access.
+//! Step results are programmable per step id, failures can be queued to
+//! exercise error handling and retries, and every request is captured.
+//!
+//! ```no_run
+//! use helios_client::dsl::PipelineBuilder;
+//! use helios_client::testing::{MockHeliosServer, StepResponse};
+//!
+//! # async fn example() -> Result<(), helios_client::HeliosClientError> {
+//! let server = MockHeliosServer::start().await;
+//! server.on_step("greet", StepResponse::ok(serde_json::json!("hello")));
+//!
+//! let pipeline = PipelineBuilder::new("test")
+//!     .script("greet", "echo hello")
+//!     .then()
+//!     .output("greet")
+//!     .build();
+//! let response = server.client().run_pipeline(pipeline).await?;
+//! assert_eq!(response.outputs[0].result_json, "hello");
+//! assert_eq!(server.requests().len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::HeliosGraphQLClient;
+
+/// The programmed result of a single step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepResponse {
+    Ok(Value),
+    Error(String),
+}
+
+impl StepResponse {
+    pub fn ok(result: Value) -> Self {
+        StepResponse::Ok(result)
+    }
+
+    pub fn error(message: &str) -> Self {
+        StepResponse::Error(message.to_string())
+    }
+}
+
+/// A failure returned instead of the normal response to the next request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockFailure {
+    /// A non-2xx HTTP response with the given body.
+    Status { status: u16, body: String },
+    /// A 200 response carrying a GraphQL error with `extensions.code`.
+    GraphQL {
+        message: String,
+        code: Option<String>,
+    },
+}
+
+impl MockFailure {
+    pub fn status(status: u16) -> Self {
+        MockFailure::Status {
+            status,
+            body: String::new(),
+        }
+    }
+
+    pub fn graphql(message: &str, code: Option<&str>) -> Self {
+        MockFailure::GraphQL {
+            message: message.to_string(),
+            code: code.map(str::to_string),
+        }
+    }
+}
+
+/// A request received by the mock server.
+#[derive(Debug, Clone)]
+pub struct CapturedRequest {
+    pub operation_name: Option<String>,
+    pub query: String,
+    pub variables: Value,
+    /// Header names are lowercase.
+    pub headers: HashMap<String, String>,
+}
+
+impl CapturedRequest {
+    /// The `pipeline` variable of `runPipeline`/`createPipeline` requests.
+    pub fn pipeline(&self) -> Option<&Value> {
+        self.variables.get("pipeline")
+    }
+}
+
+#[derive(Default)]
+struct State {
+    steps: HashMap<String, StepResponse>,
+    failures: VecDeque<MockFailure>,
+    requests: Vec<CapturedRequest>,
+    hello: Option<String>,
+    created: usize,
+}
+
+/// In-process mock Helios GraphQL server. Shuts down when dropped.
+pub struct MockHeliosServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockHeliosServer {
+    /// Starts the server on an ephemeral `127.0.0.1` port.
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .expect("failed to bind mock Helios server");
+        let addr = listener.local_addr().expect("mock server has no address");
+        let state = Arc::new(Mutex::new(State::default()));
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+
+        let handler_state = state.clone();
+        let router = axum::Router::new().fallback(move |headers: HeaderMap, body: Json<Value>| {
+            let state = handler_state.clone();
+            async move { handle(&state, headers, body.0) }
+        });
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The GraphQL endpoint URL. Any path is accepted; this one mirrors a
+    /// real Helios deployment.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}/api/graphql", self.addr)
+    }
+
+    /// A client pointing at this server.
+    pub fn client(&self) -> HeliosGraphQLClient {
+        HeliosGraphQLClient::new(self.endpoint())
+    }
+
+    /// Programs the result returned for `step_id` in every later run.
+    /// Unprogrammed steps succeed with a `null` result.
+    pub fn on_step(&self, step_id: &str, response: StepResponse) {
+        self.lock().steps.insert(step_id.to_string(), response);
+    }
+
+    /// Sets the reply to the `hello` query.
+    pub fn set_hello(&self, message: &str) {
+        self.lock().hello = Some(message.to_string());
+    }
+
+    /// Queues a failure; each queued failure answers exactly one request, in
+    /// order, before normal responses resume.
+    pub fn fail_next(&self, failure: MockFailure) {
+        self.lock().failures.push_back(failure);
+    }
+
+    /// All requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<CapturedRequest> {
+        self.lock().requests.clone()
+    }
+
+    pub fn clear_requests(&self) {
+        self.lock().requests.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockHeliosServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+fn handle(state: &Mutex<State>, headers: HeaderMap, body: Value) -> (StatusCode, Json<Value>) {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let request = CapturedRequest {
+        operation_name: body
+            .get("operationName")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        query: body
+            .get("query")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        variables: body.get("variables").cloned().unwrap_or(Value::Null),
+        headers: headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+    };
+    state.requests.push(request.clone());
+
+    if let Some(failure) = state.failures.pop_front() {
+        return match failure {
+            MockFailure::Status { status, body } => (
+                StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(Value::String(body)),
+            ),
+            MockFailure::GraphQL { message, code } => {
+                let mut error = json!({ "message": message });
+                if let Some(code) = code {
+                    error["extensions"] = json!({ "code": code });
+                }
+                (
+                    StatusCode::OK,
+                    Json(json!({ "data": null, "errors": [error] })),
+                )
+            }
+        };
+    }
+
+    let operation = request
+        .operation_name
+        .clone()
+        .unwrap_or_else(|| operation_from_query(&request.query));
+    let data = match operation.as_str() {
+        "RunPipeline" | "runPipeline" => run_pipeline(&state, request.pipeline()),
+        "CreatePipeline" | "createPipeline" => {
+            state.created += 1;
+            create_pipeline(state.created, request.pipeline())
+        }
+        "Hello" | "hello" => json!({
+            "hello": state.hello.clone().unwrap_or_else(|| "Hello from mock Helios".to_string())
+        }),
+        other => {
+            let error = json!({ "message": format!("mock server does not implement `{}`", other) });
+            return (
+                StatusCode::OK,
+                Json(json!({ "data": null, "errors": [error] })),
+            );
+        }
+    };
+    (StatusCode::OK, Json(json!({ "data": data })))
+}
+
+fn operation_from_query(query: &str) -> String {
+    ["runPipeline", "createPipeline", "hello"]
+        .into_iter()
+        .find(|field| query.contains(field))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn run_pipeline(state: &State, pipeline: Option<&Value>) -> Value {
+    let pipeline = pipeline.cloned().unwrap_or(Value::Null);
+    let step_ids: Vec<String> = pipeline["steps"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|step| step["id"].as_str().map(str::to_string))
+        .collect();
+    let requested: Vec<String> = pipeline["outputs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_str().map(str::to_string))
+        .collect();
+    let reported = if requested.is_empty() {
+        step_ids
+    } else {
+        requested
+    };
+
+    let mut success = true;
+    let outputs: Vec<Value> = reported
+        .iter()
+        .map(|id| match state.steps.get(id) {
+            Some(StepResponse::Error(error)) => {
+                success = false;
+                json!({ "stepId": id, "resultJson": null, "error": error })
+            }
+            Some(StepResponse::Ok(result)) => {
+                json!({ "stepId": id, "resultJson": result, "error": null })
+            }
+            None => json!({ "stepId": id, "resultJson": null, "error": null }),
+        })
+        .collect();
+
+    json!({
+        "runPipeline": {
+            "success": success,
+            "message": if success { "Pipeline executed successfully" } else { "Pipeline failed" },
+            "outputs": outputs,
+        }
+    })
+}
+
+fn create_pipeline(sequence: usize, pipeline: Option<&Value>) -> Value {
+    let pipeline = pipeline.cloned().unwrap_or(Value::Null);
+    let steps: Vec<Value> = pipeline["steps"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|step| {
+            json!({
+                "name": step["name"],
+                "type": step["type"],
+                "target": step["target"],
+                "dependsOn": step["dependsOn"],
+                "expression": step["expression"],
+                "data": step["data"],
+            })
+        })
+        .collect();
+
+    json!({
+        "createPipeline": {
+            "id": format!("pipeline-{}", sequence),
+            "trigger": pipeline["trigger"],
+            "steps": steps,
+        }
+    })
+}
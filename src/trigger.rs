@@ -0,0 +1,409 @@
+//! Triggers for registered pipelines.
+//!
+//! A [`Trigger`] is sent to the server as a single string:
+//!
+//! | Trigger                          | Encoding                              |
+//! |----------------------------------|---------------------------------------|
+//! | `Trigger::Manual`                | `manual`                              |
+//! | `Trigger::cron("0 6 * * MON")`   | `cron:0 6 * * MON`                    |
+//! | `Trigger::webhook("/deploy", ..)`| `webhook:/deploy` or `webhook:/deploy?secret_name=s` |
+//! | `Trigger::event("orders", ..)`   | `event:orders` or `event:orders?filter=f` |
+//!
+//! The same strings are accepted in pipeline definition files.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::validation::is_secret_name;
+
+const CRON_MACROS: &[&str] = &[
+    "@yearly",
+    "@annually",
+    "@monthly",
+    "@weekly",
+    "@daily",
+    "@midnight",
+    "@hourly",
+];
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+const DAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Why a trigger is rejected.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TriggerError {
+    #[error("unknown trigger `{0}`; expected `manual`, `cron:`, `webhook:` or `event:`")]
+    UnknownKind(String),
+    #[error("invalid cron expression `{expression}`: {reason}")]
+    InvalidCron { expression: String, reason: String },
+    #[error("invalid webhook path `{0}`: must start with `/` and contain no whitespace or `?`")]
+    InvalidWebhookPath(String),
+    #[error("invalid event topic `{0}`: must be non-empty and contain no whitespace or `?`")]
+    InvalidTopic(String),
+    #[error("`{0}` must not be empty when given")]
+    EmptyParameter(&'static str),
+    #[error("invalid secret name `{0}`: use letters, digits, `_`, `-` or `.`")]
+    InvalidSecretName(String),
+}
+
+/// What starts a registered pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Trigger {
+    /// Started on demand.
+    Manual,
+    /// Started on a five-field cron schedule (minute, hour, day of month,
+    /// month, day of week) or one of the `@daily`-style macros.
+    Cron(String),
+    /// Started by a request to `path` on the Helios webhook endpoint. When
+    /// `secret_name` is set, the server verifies inbound requests with the
+    /// server-side secret of that name; the secret itself is never stored in
+    /// the pipeline.
+    Webhook {
+        path: String,
+        secret_name: Option<String>,
+    },
+    /// Started by messages published to `topic`, optionally narrowed by a
+    /// server-side `filter` expression.
+    Event {
+        topic: String,
+        filter: Option<String>,
+    },
+}
+
+impl Trigger {
+    pub fn manual() -> Self {
+        Trigger::Manual
+    }
+
+    /// A cron schedule. Runs of whitespace are collapsed so equivalent
+    /// schedules encode identically; the expression is checked by
+    /// [`validate`](Self::validate).
+    pub fn cron(expression: &str) -> Self {
+        Trigger::Cron(expression.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
+    /// A webhook trigger, verified with the secret named `secret_name`.
+    pub fn webhook(path: &str, secret_name: Option<&str>) -> Self {
+        Trigger::Webhook {
+            path: path.to_string(),
+            secret_name: secret_name.map(str::to_string),
+        }
+    }
+
+    pub fn event(topic: &str, filter: Option<&str>) -> Self {
+        Trigger::Event {
+            topic: topic.to_string(),
+            filter: filter.map(str::to_string),
+        }
+    }
+
+    /// `manual`, `cron`, `webhook` or `event`: the encoding prefix, without
+    /// the schedule, path, topic or parameters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Trigger::Manual => "manual",
+            Trigger::Cron(_) => "cron",
+            Trigger::Webhook { .. } => "webhook",
+            Trigger::Event { .. } => "event",
+        }
+    }
+
+    /// Checks the cron expression, webhook path or event topic.
+    pub fn validate(&self) -> Result<(), TriggerError> {
+        match self {
+            Trigger::Manual => Ok(()),
+            Trigger::Cron(expression) => validate_cron(expression),
+            Trigger::Webhook { path, secret_name } => {
+                if !path.starts_with('/') || !is_plain(path) {
+                    return Err(TriggerError::InvalidWebhookPath(path.clone()));
+                }
+                match secret_name {
+                    Some(name) if !is_secret_name(name) => {
+                        Err(TriggerError::InvalidSecretName(name.clone()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Trigger::Event { topic, filter } => {
+                if topic.is_empty() || !is_plain(topic) {
+                    return Err(TriggerError::InvalidTopic(topic.clone()));
+                }
+                if filter.as_deref() == Some("") {
+                    return Err(TriggerError::EmptyParameter("filter"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Manual => f.write_str("manual"),
+            Trigger::Cron(expression) => write!(f, "cron:{}", expression),
+            Trigger::Webhook { path, secret_name } => {
+                write!(f, "webhook:{}", path)?;
+                if let Some(name) = secret_name {
+                    write!(f, "?secret_name={}", name)?;
+                }
+                Ok(())
+            }
+            Trigger::Event { topic, filter } => {
+                write!(f, "event:{}", topic)?;
+                if let Some(filter) = filter {
+                    write!(f, "?filter={}", filter)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = TriggerError;
+
+    /// Parses the canonical encoding and validates the result.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trigger = if s == "manual" {
+            Trigger::Manual
+        } else if let Some(expression) = s.strip_prefix("cron:") {
+            Trigger::cron(expression)
+        } else if let Some(rest) = s.strip_prefix("webhook:") {
+            let (path, secret_name) = split_parameter(rest, "secret_name");
+            Trigger::webhook(path, secret_name)
+        } else if let Some(rest) = s.strip_prefix("event:") {
+            let (topic, filter) = split_parameter(rest, "filter");
+            Trigger::event(topic, filter)
+        } else {
+            return Err(TriggerError::UnknownKind(s.to_string()));
+        };
+        trigger.validate()?;
+        Ok(trigger)
+    }
+}
+
+impl TryFrom<String> for Trigger {
+    type Error = TriggerError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Trigger> for String {
+    fn from(trigger: Trigger) -> Self {
+        trigger.to_string()
+    }
+}
+
+/// Splits `value?name=parameter`. Everything after the marker belongs to the
+/// parameter, so filters may contain `?` and `=`.
+fn split_parameter<'a>(s: &'a str, name: &str) -> (&'a str, Option<&'a str>) {
+    let marker = format!("?{}=", name);
+    match s.split_once(&marker) {
+        Some((value, parameter)) => (value, Some(parameter)),
+        None => (s, None),
+    }
+}
+
+fn is_plain(s: &str) -> bool {
+    !s.chars().any(|c| c.is_whitespace() || c == '?')
+}
+
+fn validate_cron(expression: &str) -> Result<(), TriggerError> {
+    let invalid = |reason: String| TriggerError::InvalidCron {
+        expression: expression.to_string(),
+        reason,
+    };
+
+    if expression.starts_with('@') {
+        return if CRON_MACROS.contains(&expression) {
+            Ok(())
+        } else {
+            Err(invalid(format!("unknown macro `{}`", expression)))
+        };
+    }
+
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(invalid(format!(
+            "expected 5 fields, found {}",
+            fields.len()
+        )));
+    }
+    let specs: [(&str, u32, u32, &[&str]); 5] = [
+        ("minute", 0, 59, &[]),
+        ("hour", 0, 23, &[]),
+        ("day of month", 1, 31, &[]),
+        ("month", 1, 12, MONTH_NAMES),
+        // 7 is accepted as a second spelling of Sunday.
+        ("day of week", 0, 7, DAY_NAMES),
+    ];
+    for (field, (name, min, max, names)) in fields.iter().zip(specs) {
+        validate_cron_field(field, min, max, names)
+            .map_err(|reason| invalid(format!("{} field `{}`: {}", name, field, reason)))?;
+    }
+    Ok(())
+}
+
+fn validate_cron_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<(), String> {
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        if let Some(step) = step {
+            match step.parse::<u32>() {
+                Ok(step) if step > 0 => {}
+                _ => return Err(format!("invalid step `{}`", step)),
+            }
+        }
+        if range == "*" {
+            continue;
+        }
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start, Some(end)),
+            None => (range, None),
+        };
+        let start = cron_value(start, min, max, names)?;
+        if let Some(end) = end {
+            let end = cron_value(end, min, max, names)?;
+            if start > end {
+                return Err(format!("range `{}` is reversed", range));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cron_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    // Names map to their position: JAN is 1, SUN is 0.
+    let offset = if min == 1 { 1 } else { 0 };
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(index as u32 + offset);
+    }
+    match value.parse::<u32>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        Ok(n) => Err(format!("{} is outside {}-{}", n, min, max)),
+        Err(_) => Err(format!("`{}` is not a number", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trips(trigger: Trigger, encoded: &str) {
+        trigger.validate().unwrap();
+        assert_eq!(trigger.to_string(), encoded);
+        assert_eq!(encoded.parse::<Trigger>().unwrap(), trigger);
+    }
+
+    #[test]
+    fn manual_and_cron_triggers_round_trip() {
+        round_trips(Trigger::manual(), "manual");
+        round_trips(Trigger::cron("@daily"), "cron:@daily");
+    }
+
+    #[test]
+    fn cron_whitespace_is_collapsed() {
+        round_trips(Trigger::cron("0  6 * *\tMON-FRI"), "cron:0 6 * * MON-FRI");
+    }
+
+    #[test]
+    fn webhook_triggers_round_trip_with_and_without_a_secret_name() {
+        round_trips(
+            Trigger::webhook("/hooks/deploy", None),
+            "webhook:/hooks/deploy",
+        );
+        round_trips(
+            Trigger::webhook("/hooks/deploy", Some("deploy_hook")),
+            "webhook:/hooks/deploy?secret_name=deploy_hook",
+        );
+    }
+
+    #[test]
+    fn webhook_secret_names_must_name_a_secret() {
+        for name in ["", "not a secret", "s3cret!"] {
+            assert_eq!(
+                Trigger::webhook("/hooks/deploy", Some(name)).validate(),
+                Err(TriggerError::InvalidSecretName(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn event_filters_are_kept_verbatim() {
+        round_trips(
+            Trigger::event("orders", Some("amount > 10 && region == 'eu'")),
+            "event:orders?filter=amount > 10 && region == 'eu'",
+        );
+    }
+
+    #[test]
+    fn accepts_lists_ranges_steps_and_names_in_cron_fields() {
+        assert!(Trigger::cron("*/15 0-6,22 1 jan,JUL 0,7")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_cron_expressions() {
+        for cron in [
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * FOO *",
+            "@often",
+        ] {
+            assert!(
+                matches!(
+                    Trigger::cron(cron).validate(),
+                    Err(TriggerError::InvalidCron { .. })
+                ),
+                "{cron} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn kinds_name_the_encoding_prefix() {
+        for trigger in [
+            Trigger::manual(),
+            Trigger::cron("@daily"),
+            Trigger::webhook("/deploy", Some("deploy_hook")),
+            Trigger::event("orders", None),
+        ] {
+            assert!(trigger.to_string().starts_with(trigger.kind()));
+        }
+        assert_eq!(
+            Trigger::webhook("/deploy", Some("deploy_hook")).kind(),
+            "webhook"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_kinds_and_relative_webhook_paths() {
+        assert!(matches!(
+            "hourly".parse::<Trigger>(),
+            Err(TriggerError::UnknownKind(_))
+        ));
+        assert!(matches!(
+            Trigger::webhook("hooks/deploy", None).validate(),
+            Err(TriggerError::InvalidWebhookPath(_))
+        ));
+    }
+}
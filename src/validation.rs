@@ -0,0 +1,1547 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::container::{ContainerSpec, VolumeSource};
+use crate::dsl::{ActionType, LLMWorkflowTypes, Pipeline, Step};
+use crate::expression::{Expr, ExpressionError};
+use crate::llm::{LlmSpec, Tool, ToolBinding, Worker, TEMPERATURE_RANGE};
+use crate::template::{self, TemplateError};
+use crate::trigger::{Trigger, TriggerError};
+use crate::webhook::{HttpMethod, WebhookSpec};
+
+/// What is wrong with a single part of a pipeline definition.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    #[error("pipeline name is empty")]
+    EmptyPipelineName,
+    #[error("step id is empty")]
+    EmptyStepId,
+    #[error("step id `{0}` is used by more than one step")]
+    DuplicateStepId(String),
+    #[error("depends on unknown step `{0}`")]
+    UnknownDependency(String),
+    #[error("dependency `{0}` is listed more than once")]
+    DuplicateDependency(String),
+    #[error("step depends on itself")]
+    SelfDependency,
+    #[error("dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+    #[error("output references unknown step `{0}`")]
+    UnknownOutput(String),
+    #[error("output `{0}` is declared more than once")]
+    DuplicateOutput(String),
+    #[error(transparent)]
+    InvalidTrigger(TriggerError),
+    #[error("invalid expression: {0}")]
+    InvalidExpression(ExpressionError),
+    #[error("invalid prompt template: {0}")]
+    InvalidTemplate(TemplateError),
+    #[error("references unknown step `{0}`")]
+    UnknownReference(String),
+    #[error("references step `{0}`, which is not upstream of this step")]
+    NonUpstreamReference(String),
+    #[error("value `{value}` is outside {expected}")]
+    OutOfRange { value: String, expected: String },
+    #[error("must not be empty")]
+    EmptyValue,
+    #[error("container path `{0}` is mounted more than once")]
+    DuplicateMountPath(String),
+    #[error("{volume} is also mounted by concurrent step `{other_step}` and a mount is writable")]
+    ConflictingMount {
+        volume: VolumeSource,
+        other_step: String,
+    },
+    #[error("tool name `{0}` must be 1 to 64 letters, digits, `_` or `-`")]
+    InvalidToolName(String),
+    #[error("tool `{0}` is defined more than once")]
+    DuplicateTool(String),
+    #[error("tool parameters must be a JSON Schema of `type: object`")]
+    InvalidToolParameters,
+    #[error("tools are only used by `agent_executor` workflows, not `{0}`")]
+    ToolsRequireAgent(String),
+    #[error("must be set")]
+    MissingValue,
+    #[error("route target `{0}` does not depend on this step")]
+    RouteTargetNotDownstream(String),
+    #[error("worker `{0}` is defined more than once")]
+    DuplicateWorker(String),
+    #[error("invalid step data: {0}")]
+    InvalidStepData(String),
+    #[error("unknown tool binding type `{0}`")]
+    UnknownToolBinding(String),
+    #[error("`{0}` is not an http(s) URL")]
+    InvalidUrl(String),
+    #[error("{0} requests cannot carry a body")]
+    BodyNotAllowed(HttpMethod),
+    #[error("secret name `{0}` must be letters, digits, `_`, `-` or `.`")]
+    InvalidSecretName(String),
+    #[error("an `authorization` header conflicts with `auth`")]
+    ConflictingAuthorization,
+}
+
+/// A single problem found by [`Pipeline::validate`].
+///
+/// `step` is `None` for pipeline-level problems (e.g. the pipeline name or
+/// the `outputs` list), and `field` names the offending attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub step: Option<String>,
+    pub field: String,
+    pub kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    fn step(step: &str, field: &str, kind: ValidationErrorKind) -> Self {
+        Self {
+            step: Some(step.to_string()),
+            field: field.to_string(),
+            kind,
+        }
+    }
+
+    fn pipeline(field: &str, kind: ValidationErrorKind) -> Self {
+        Self {
+            step: None,
+            field: field.to_string(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.step {
+            Some(step) => write!(f, "step `{}`, field `{}`: {}", step, self.field, self.kind),
+            None => write!(f, "pipeline field `{}`: {}", self.field, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// All problems found in a pipeline definition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<ValidationError>);
+
+impl ValidationErrors {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ValidationError> {
+        self.0.iter()
+    }
+
+    /// Returns the errors reported for the given step id.
+    pub fn for_step<'a>(&'a self, step: &'a str) -> impl Iterator<Item = &'a ValidationError> {
+        self.0
+            .iter()
+            .filter(move |e| e.step.as_deref() == Some(step))
+    }
+
+    fn push(&mut self, error: ValidationError) {
+        self.0.push(error);
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pipeline validation error(s)", self.0.len())?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl IntoIterator for ValidationErrors {
+    type Item = ValidationError;
+    type IntoIter = std::vec::IntoIter<ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ValidationErrors {
+    type Item = &'a ValidationError;
+    type IntoIter = std::slice::Iter<'a, ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Pipeline {
+    /// Checks the pipeline graph for problems the server would otherwise
+    /// reject: empty or duplicate step ids, dependencies and outputs naming
+    /// unknown steps, dependency cycles, malformed triggers, LLM prompt
+    /// templates that fail to parse or read steps that are not upstream,
+    /// container volumes shared unsafely, and LLM settings out of range.
+    /// `when` expressions are checked by [`warnings`](Self::warnings).
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::pipeline(
+                "name",
+                ValidationErrorKind::EmptyPipelineName,
+            ));
+        }
+
+        if let Some(Err(e)) = self.trigger.as_ref().map(Trigger::validate) {
+            errors.push(ValidationError::pipeline(
+                "trigger",
+                ValidationErrorKind::InvalidTrigger(e),
+            ));
+        }
+
+        let known = self.check_graph(&mut errors);
+
+        for step in &self.steps {
+            if !matches!(step.type_, ActionType::LlmWorkflow) {
+                continue;
+            }
+            // Undeserializable data is reported by `check_llm_settings`.
+            let Ok(spec) = LlmSpec::from_data(&step.data) else {
+                continue;
+            };
+            let id = step.effective_id();
+            for (field, prompt) in spec.templates() {
+                let field = field.as_str();
+                match template::step_refs(prompt) {
+                    Ok(refs) => self.check_references(id, &refs, &known, &mut |kind| {
+                        errors.push(ValidationError::step(id, field, kind))
+                    }),
+                    Err(e) => errors.push(ValidationError::step(
+                        id,
+                        field,
+                        ValidationErrorKind::InvalidTemplate(e),
+                    )),
+                }
+            }
+        }
+
+        self.check_containers(&mut errors);
+        self.check_llm_settings(&mut errors);
+        self.check_webhooks(&mut errors);
+
+        let mut seen_outputs: HashSet<&str> = HashSet::new();
+        for output in &self.outputs {
+            if !known.contains(output.as_str()) {
+                errors.push(ValidationError::pipeline(
+                    "outputs",
+                    ValidationErrorKind::UnknownOutput(output.clone()),
+                ));
+            } else if !seen_outputs.insert(output) {
+                errors.push(ValidationError::pipeline(
+                    "outputs",
+                    ValidationErrorKind::DuplicateOutput(output.clone()),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Pipeline {
+    /// Checks only the dependency graph: empty or duplicate step ids,
+    /// dependencies naming unknown steps, and dependency cycles.
+    pub(crate) fn validate_graph(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.check_graph(&mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reports the problems [`validate_graph`](Self::validate_graph) checks
+    /// for, returning the known step ids.
+    fn check_graph(&self, errors: &mut ValidationErrors) -> HashSet<&str> {
+        let mut known: HashSet<&str> = HashSet::new();
+        for step in &self.steps {
+            let id = step.effective_id();
+            if id.trim().is_empty() {
+                errors.push(ValidationError::step(
+                    id,
+                    "id",
+                    ValidationErrorKind::EmptyStepId,
+                ));
+            } else if !known.insert(id) {
+                errors.push(ValidationError::step(
+                    id,
+                    "id",
+                    ValidationErrorKind::DuplicateStepId(id.to_string()),
+                ));
+            }
+        }
+
+        for step in &self.steps {
+            let id = step.effective_id();
+            let mut seen: HashSet<&str> = HashSet::new();
+            for dependency in &step.depends_on {
+                let dependency = dependency.as_str();
+                if !seen.insert(dependency) {
+                    errors.push(ValidationError::step(
+                        id,
+                        "depends_on",
+                        ValidationErrorKind::DuplicateDependency(dependency.to_string()),
+                    ));
+                } else if dependency == id {
+                    errors.push(ValidationError::step(
+                        id,
+                        "depends_on",
+                        ValidationErrorKind::SelfDependency,
+                    ));
+                } else if !known.contains(dependency) {
+                    errors.push(ValidationError::step(
+                        id,
+                        "depends_on",
+                        ValidationErrorKind::UnknownDependency(dependency.to_string()),
+                    ));
+                }
+            }
+        }
+
+        for cycle in find_cycles(&self.steps, &known) {
+            let first = cycle[0].clone();
+            errors.push(ValidationError::step(
+                &first,
+                "depends_on",
+                ValidationErrorKind::DependencyCycle(cycle),
+            ));
+        }
+
+        known
+    }
+
+    /// Problems that do not stop the pipeline from being sent but likely
+    /// make it misbehave: `when` expressions this client cannot parse, and
+    /// expressions reading steps that are unknown or not upstream.
+    ///
+    /// The server's expression language may accept more than
+    /// [`crate::expression`] does, so these are not reported by
+    /// [`validate`](Self::validate).
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        let known: HashSet<&str> = self.steps.iter().map(Step::effective_id).collect();
+        let mut warnings = Vec::new();
+        for step in &self.steps {
+            let Some(expression) = &step.expression else {
+                continue;
+            };
+            let id = step.effective_id();
+            let mut report = |kind| warnings.push(ValidationError::step(id, "expression", kind));
+            match Expr::parse(expression) {
+                Ok(expr) => self.check_references(id, &expr.step_refs(), &known, &mut report),
+                Err(e) => report(ValidationErrorKind::InvalidExpression(e)),
+            }
+        }
+        warnings
+    }
+
+    /// Reports `refs` read by step `id` that are unknown or not upstream.
+    fn check_references(
+        &self,
+        id: &str,
+        refs: &[&str],
+        known: &HashSet<&str>,
+        report: &mut impl FnMut(ValidationErrorKind),
+    ) {
+        let upstream = self.upstream_of(id);
+        for reference in refs {
+            let kind = if !known.contains(reference) {
+                ValidationErrorKind::UnknownReference(reference.to_string())
+            } else if !upstream.contains(reference) {
+                ValidationErrorKind::NonUpstreamReference(reference.to_string())
+            } else {
+                continue;
+            };
+            report(kind);
+        }
+    }
+
+    /// Reports LLM step data that does not deserialize, and generation
+    /// settings outside the ranges providers accept.
+    fn check_llm_settings(&self, errors: &mut ValidationErrors) {
+        for step in &self.steps {
+            if !matches!(step.type_, ActionType::LlmWorkflow) {
+                continue;
+            }
+            let id = step.effective_id();
+            let mut report = |field: &str, kind: ValidationErrorKind| {
+                errors.push(ValidationError::step(id, field, kind));
+            };
+            let spec = match LlmSpec::from_data(&step.data) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    let unknown = unknown_tool_bindings(&step.data);
+                    if unknown.is_empty() {
+                        report("data", ValidationErrorKind::InvalidStepData(e.to_string()));
+                    }
+                    for (tool, binding) in unknown {
+                        report(
+                            &format!("tools.{}.binding", tool),
+                            ValidationErrorKind::UnknownToolBinding(binding),
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(t) = spec.temperature.filter(|t| !TEMPERATURE_RANGE.contains(t)) {
+                report(
+                    "temperature",
+                    ValidationErrorKind::OutOfRange {
+                        value: t.to_string(),
+                        expected: format!("{:?}", TEMPERATURE_RANGE),
+                    },
+                );
+            }
+            if let Some(p) = spec.top_p.filter(|p| !(*p > 0.0 && *p <= 1.0)) {
+                report(
+                    "top_p",
+                    ValidationErrorKind::OutOfRange {
+                        value: p.to_string(),
+                        expected: "(0.0, 1.0]".to_string(),
+                    },
+                );
+            }
+            if spec.max_tokens == Some(0) {
+                report(
+                    "max_tokens",
+                    ValidationErrorKind::OutOfRange {
+                        value: "0".to_string(),
+                        expected: "1..".to_string(),
+                    },
+                );
+            }
+            if spec.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+                report("model", ValidationErrorKind::EmptyValue);
+            }
+            if spec.stop_sequences.iter().any(String::is_empty) {
+                report("stop_sequences", ValidationErrorKind::EmptyValue);
+            }
+            let workflow = step
+                .target
+                .as_deref()
+                .and_then(LLMWorkflowTypes::from_target);
+            check_tools(&spec.tools, step.target.as_deref(), workflow, &mut report);
+
+            match workflow {
+                Some(LLMWorkflowTypes::Routing) => self.check_routes(id, &spec, &mut report),
+                Some(LLMWorkflowTypes::Orchestrator) => check_workers(&spec.workers, &mut report),
+                Some(LLMWorkflowTypes::PromptChain)
+                    if spec.prompts.is_empty()
+                        || spec.prompts.iter().any(|p| p.trim().is_empty()) =>
+                {
+                    report("prompts", ValidationErrorKind::EmptyValue)
+                }
+                Some(LLMWorkflowTypes::EvaluationOptimization) => {
+                    if spec.criteria.is_empty() || spec.criteria.iter().any(|c| c.trim().is_empty())
+                    {
+                        report("criteria", ValidationErrorKind::EmptyValue);
+                    }
+                    match spec.max_iterations {
+                        None => report("max_iterations", ValidationErrorKind::MissingValue),
+                        Some(0) => report(
+                            "max_iterations",
+                            ValidationErrorKind::OutOfRange {
+                                value: "0".to_string(),
+                                expected: "1..".to_string(),
+                            },
+                        ),
+                        Some(_) => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Reports missing routes, empty conditions, and targets that are
+    /// unknown or do not depend on the routing step `id`.
+    fn check_routes(
+        &self,
+        id: &str,
+        spec: &LlmSpec,
+        report: &mut impl FnMut(&str, ValidationErrorKind),
+    ) {
+        if spec.routes.is_empty() {
+            report("routes", ValidationErrorKind::EmptyValue);
+        }
+        let targets = spec
+            .routes
+            .iter()
+            .map(|route| ("routes", route.target.as_str()))
+            .chain(spec.default_route.as_deref().map(|t| ("default_route", t)));
+        for (field, target) in targets {
+            if !self.steps.iter().any(|s| s.effective_id() == target) {
+                report(
+                    field,
+                    ValidationErrorKind::UnknownReference(target.to_string()),
+                );
+            } else if !self.upstream_of(target).contains(id) {
+                report(
+                    field,
+                    ValidationErrorKind::RouteTargetNotDownstream(target.to_string()),
+                );
+            }
+        }
+        for route in &spec.routes {
+            if route.condition.trim().is_empty() {
+                report(
+                    &format!("routes.{}.condition", route.target),
+                    ValidationErrorKind::EmptyValue,
+                );
+            }
+        }
+    }
+
+    /// Reports webhook settings the request could not be sent with.
+    fn check_webhooks(&self, errors: &mut ValidationErrors) {
+        for step in &self.steps {
+            if !matches!(step.type_, ActionType::Webhook) {
+                continue;
+            }
+            let id = step.effective_id();
+            let mut report = |field: &str, kind: ValidationErrorKind| {
+                errors.push(ValidationError::step(id, field, kind));
+            };
+            let spec = match WebhookSpec::from_data(&step.data) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    report("data", ValidationErrorKind::InvalidStepData(e.to_string()));
+                    continue;
+                }
+            };
+
+            // Templated URLs are only known once the run resolves them.
+            let url = spec.url.trim();
+            if url.is_empty() {
+                report("url", ValidationErrorKind::EmptyValue);
+            } else if !(url.starts_with("http://")
+                || url.starts_with("https://")
+                || url.starts_with("{{"))
+            {
+                report("url", ValidationErrorKind::InvalidUrl(spec.url.clone()));
+            }
+            if spec.body.as_ref().is_some_and(|b| !b.is_null()) && !spec.method.allows_body() {
+                report("body", ValidationErrorKind::BodyNotAllowed(spec.method));
+            }
+            if let Some(auth) = &spec.auth {
+                let secret = auth.secret();
+                if !is_secret_name(secret) {
+                    report(
+                        "auth",
+                        ValidationErrorKind::InvalidSecretName(secret.to_string()),
+                    );
+                }
+                if spec.header("authorization").is_some() {
+                    report("headers", ValidationErrorKind::ConflictingAuthorization);
+                }
+            }
+            if spec.timeout == Some(0) {
+                report(
+                    "timeout",
+                    ValidationErrorKind::OutOfRange {
+                        value: "0".to_string(),
+                        expected: "1..".to_string(),
+                    },
+                );
+            }
+            for status in spec
+                .expected_status
+                .iter()
+                .filter(|s| !(100..=599).contains(*s))
+            {
+                report(
+                    "expected_status",
+                    ValidationErrorKind::OutOfRange {
+                        value: status.to_string(),
+                        expected: "100..=599".to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Reports container step data that does not deserialize, container
+    /// paths mounted twice by one step, host paths or
+    /// named volumes shared by steps that may run concurrently (neither is
+    /// upstream of the other) when either mount is writable, and registry
+    /// credentials naming an invalid secret. Scratch volumes are private to
+    /// their step.
+    fn check_containers(&self, errors: &mut ValidationErrors) {
+        let dependencies = self.dependency_map();
+        // Upstream sets of the container steps seen so far, each computed once.
+        let mut upstream_sets: HashMap<&str, HashSet<&str>> = HashMap::new();
+        let mut shared: Vec<(&str, VolumeSource, bool)> = Vec::new();
+        for step in &self.steps {
+            if !matches!(step.type_, ActionType::NimbusContainer) {
+                continue;
+            }
+            let id = step.effective_id();
+            let spec = match ContainerSpec::from_data(&step.data) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    errors.push(ValidationError::step(
+                        id,
+                        "data",
+                        ValidationErrorKind::InvalidStepData(e.to_string()),
+                    ));
+                    continue;
+                }
+            };
+            if let Some(auth) = spec.registry_auth.as_ref() {
+                if !is_secret_name(&auth.password_secret) {
+                    errors.push(ValidationError::step(
+                        id,
+                        "registry_auth",
+                        ValidationErrorKind::InvalidSecretName(auth.password_secret.clone()),
+                    ));
+                }
+            }
+            let mut paths: HashSet<&str> = HashSet::new();
+            for mount in &spec.volumes {
+                if !paths.insert(&mount.container_path) {
+                    errors.push(ValidationError::step(
+                        id,
+                        "volumes",
+                        ValidationErrorKind::DuplicateMountPath(mount.container_path.clone()),
+                    ));
+                }
+            }
+
+            let upstream = upstream_in(&dependencies, id);
+            for mount in &spec.volumes {
+                if matches!(mount.source, VolumeSource::Scratch { .. }) {
+                    continue;
+                }
+                let conflict = shared.iter().find(|(other, source, read_only)| {
+                    *source == mount.source
+                        && *other != id
+                        && !(*read_only && mount.read_only)
+                        && !upstream.contains(other)
+                        && !upstream_sets.get(other).is_some_and(|u| u.contains(id))
+                });
+                if let Some((other, _, _)) = conflict {
+                    errors.push(ValidationError::step(
+                        id,
+                        "volumes",
+                        ValidationErrorKind::ConflictingMount {
+                            volume: mount.source.clone(),
+                            other_step: other.to_string(),
+                        },
+                    ));
+                }
+            }
+            shared.extend(
+                spec.volumes
+                    .into_iter()
+                    .map(|mount| (id, mount.source, mount.read_only)),
+            );
+            upstream_sets.insert(id, upstream);
+        }
+    }
+
+    /// Ids of every step `step_id` depends on, directly or transitively.
+    /// Unknown ids and cycles are tolerated; the step itself is only
+    /// included when it sits on a cycle.
+    pub(crate) fn upstream_of(&self, step_id: &str) -> HashSet<&str> {
+        upstream_in(&self.dependency_map(), step_id)
+    }
+
+    /// The `depends_on` list of each step, by step id.
+    fn dependency_map(&self) -> HashMap<&str, &[String]> {
+        self.steps
+            .iter()
+            .map(|step| (step.effective_id(), step.depends_on.as_slice()))
+            .collect()
+    }
+}
+
+/// [`Pipeline::upstream_of`] over a prebuilt [`Pipeline::dependency_map`].
+fn upstream_in<'a>(
+    dependencies: &HashMap<&'a str, &'a [String]>,
+    step_id: &str,
+) -> HashSet<&'a str> {
+    let mut upstream = HashSet::new();
+    let mut pending: Vec<&str> = vec![step_id];
+    while let Some(id) = pending.pop() {
+        for dependency in dependencies.get(id).copied().unwrap_or_default() {
+            if upstream.insert(dependency.as_str()) {
+                pending.push(dependency);
+            }
+        }
+    }
+    upstream
+}
+
+/// Whether `name` can name a server-side secret.
+pub(crate) fn is_secret_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Tools in raw LLM step `data` whose `binding.type` is not a
+/// [`ToolBinding`], as `(tool name, binding type)` pairs.
+fn unknown_tool_bindings(data: &serde_json::Value) -> Vec<(String, String)> {
+    let tools = data.get("tools").and_then(serde_json::Value::as_array);
+    tools
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| {
+            let binding = tool.pointer("/binding/type")?.as_str()?;
+            if ToolBinding::TYPES.contains(&binding) {
+                return None;
+            }
+            let name = tool.get("name").and_then(serde_json::Value::as_str);
+            Some((name.unwrap_or_default().to_string(), binding.to_string()))
+        })
+        .collect()
+}
+
+/// Checks the tools of an LLM step whose `target` names `workflow`.
+fn check_tools(
+    tools: &[Tool],
+    target: Option<&str>,
+    workflow: Option<LLMWorkflowTypes>,
+    report: &mut impl FnMut(&str, ValidationErrorKind),
+) {
+    if tools.is_empty() {
+        return;
+    }
+    if workflow != Some(LLMWorkflowTypes::Agent) {
+        let target = target.unwrap_or("generic");
+        report(
+            "tools",
+            ValidationErrorKind::ToolsRequireAgent(target.to_string()),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    for tool in tools {
+        let valid_name = (1..=64).contains(&tool.name.len())
+            && tool
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            report(
+                "tools",
+                ValidationErrorKind::InvalidToolName(tool.name.clone()),
+            );
+        }
+        if !seen.insert(tool.name.as_str()) {
+            report(
+                "tools",
+                ValidationErrorKind::DuplicateTool(tool.name.clone()),
+            );
+        }
+
+        let field = |name: &str| format!("tools.{}.{}", tool.name, name);
+        if tool.description.trim().is_empty() {
+            report(&field("description"), ValidationErrorKind::EmptyValue);
+        }
+        if tool
+            .parameters
+            .get("type")
+            .and_then(serde_json::Value::as_str)
+            != Some("object")
+        {
+            report(
+                &field("parameters"),
+                ValidationErrorKind::InvalidToolParameters,
+            );
+        }
+        match &tool.binding {
+            ToolBinding::Webhook { url, .. } if url.trim().is_empty() => {
+                report(&field("url"), ValidationErrorKind::EmptyValue)
+            }
+            ToolBinding::Container { image, .. } if image.trim().is_empty() => {
+                report(&field("image"), ValidationErrorKind::EmptyValue)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks the workers of an `orchestrator` step.
+fn check_workers(workers: &[Worker], report: &mut impl FnMut(&str, ValidationErrorKind)) {
+    if workers.is_empty() {
+        report("workers", ValidationErrorKind::EmptyValue);
+    }
+    let mut seen = HashSet::new();
+    for worker in workers {
+        if worker.name.trim().is_empty() {
+            report("workers", ValidationErrorKind::EmptyValue);
+        } else if !seen.insert(worker.name.as_str()) {
+            report(
+                "workers",
+                ValidationErrorKind::DuplicateWorker(worker.name.clone()),
+            );
+        }
+        if worker.instructions.trim().is_empty() {
+            report(
+                &format!("workers.{}.instructions", worker.name),
+                ValidationErrorKind::EmptyValue,
+            );
+        }
+    }
+}
+
+/// Finds dependency cycles between known steps. Each cycle is reported once,
+/// starting and ending with the same step id. Self-dependencies are reported
+/// separately and skipped here.
+fn find_cycles(steps: &[Step], known: &HashSet<&str>) -> Vec<Vec<String>> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for step in steps {
+        let id = step.effective_id();
+        let targets = edges.entry(id).or_default();
+        for dependency in &step.depends_on {
+            let dependency = dependency.as_str();
+            if dependency != id && known.contains(dependency) && !targets.contains(&dependency) {
+                targets.push(dependency);
+            }
+        }
+    }
+
+    // Depth-first, with an explicit stack so long dependency chains cannot
+    // overflow the call stack. `path` holds each step on the current path
+    // and the index of its next dependency to visit; `on_path` maps those
+    // steps to their position in `path`.
+    let mut cycles = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut on_path: HashMap<&str, usize> = HashMap::new();
+    let mut path: Vec<(&str, usize)> = Vec::new();
+    for step in steps {
+        let root = step.effective_id();
+        if done.contains(root) {
+            continue;
+        }
+        on_path.insert(root, 0);
+        path.push((root, 0));
+        while let Some(&(id, next)) = path.last() {
+            let Some(&dependency) = edges.get(id).and_then(|targets| targets.get(next)) else {
+                path.pop();
+                on_path.remove(id);
+                done.insert(id);
+                continue;
+            };
+            if let Some((_, next)) = path.last_mut() {
+                *next += 1;
+            }
+            if done.contains(dependency) {
+                continue;
+            }
+            if let Some(&pos) = on_path.get(dependency) {
+                let mut cycle: Vec<String> =
+                    path[pos..].iter().map(|(s, _)| s.to_string()).collect();
+                cycle.push(dependency.to_string());
+                cycles.push(cycle);
+            } else {
+                on_path.insert(dependency, path.len());
+                path.push((dependency, 0));
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::VolumeMount;
+    use crate::dsl::PipelineBuilder;
+
+    fn kinds(builder: PipelineBuilder) -> Vec<ValidationErrorKind> {
+        match builder.try_build() {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| e.kind).collect(),
+        }
+    }
+
+    #[test]
+    fn valid_graph_passes() {
+        let builder = PipelineBuilder::new("ok")
+            .script("a", "echo a")
+            .then()
+            .script("b", "echo b")
+            .depends_on("a")
+            .then()
+            .output("b");
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn reports_each_cycle_once() {
+        let builder = PipelineBuilder::new("cycle")
+            .script("a", "echo a")
+            .depends_on("c")
+            .then()
+            .script("b", "echo b")
+            .depends_on("a")
+            .then()
+            .script("c", "echo c")
+            .depends_on("b")
+            .then();
+        assert_eq!(
+            kinds(builder),
+            [ValidationErrorKind::DependencyCycle(vec![
+                "a".to_string(),
+                "c".to_string(),
+                "b".to_string(),
+                "a".to_string(),
+            ])]
+        );
+    }
+
+    #[test]
+    fn finds_cycles_through_long_dependency_chains() {
+        let steps = 100_000;
+        let mut builder = PipelineBuilder::new("chain")
+            .script("s0", "echo 0")
+            .depends_on(&format!("s{}", steps - 1))
+            .then();
+        for i in 1..steps {
+            builder = builder
+                .script(&format!("s{}", i), "echo")
+                .depends_on(&format!("s{}", i - 1))
+                .then();
+        }
+
+        let found = kinds(builder);
+
+        assert!(matches!(
+            &found[..],
+            [ValidationErrorKind::DependencyCycle(cycle)] if cycle.len() == steps + 1
+        ));
+    }
+
+    #[test]
+    fn reports_self_dependency_separately_from_cycles() {
+        let builder = PipelineBuilder::new("self")
+            .script("a", "echo a")
+            .depends_on("a")
+            .then();
+        assert_eq!(kinds(builder), [ValidationErrorKind::SelfDependency]);
+    }
+
+    #[test]
+    fn reports_duplicate_step_ids() {
+        let builder = PipelineBuilder::new("dup")
+            .script("a", "echo 1")
+            .then()
+            .script("a", "echo 2")
+            .then();
+        assert_eq!(
+            kinds(builder),
+            [ValidationErrorKind::DuplicateStepId("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_and_repeated_dependencies() {
+        let builder = PipelineBuilder::new("deps")
+            .script("a", "echo a")
+            .then()
+            .script("b", "echo b")
+            .depends_on("a")
+            .depends_on("a")
+            .depends_on("missing")
+            .then();
+        let errors = builder.try_build().unwrap_err();
+        assert!(errors.iter().all(|e| e.step.as_deref() == Some("b")));
+        assert_eq!(
+            errors.into_iter().map(|e| e.kind).collect::<Vec<_>>(),
+            [
+                ValidationErrorKind::DuplicateDependency("a".to_string()),
+                ValidationErrorKind::UnknownDependency("missing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_and_duplicate_outputs() {
+        let builder = PipelineBuilder::new("outputs")
+            .script("a", "echo a")
+            .then()
+            .output("a")
+            .output("a")
+            .output("missing");
+        let errors = builder.try_build().unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|e| e.step.is_none() && e.field == "outputs"));
+        assert_eq!(
+            errors.into_iter().map(|e| e.kind).collect::<Vec<_>>(),
+            [
+                ValidationErrorKind::DuplicateOutput("a".to_string()),
+                ValidationErrorKind::UnknownOutput("missing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_empty_pipeline_name_and_step_id() {
+        let builder = PipelineBuilder::new(" ").script("", "echo").then();
+        assert_eq!(
+            kinds(builder),
+            [
+                ValidationErrorKind::EmptyPipelineName,
+                ValidationErrorKind::EmptyStepId,
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_problems_are_warnings_not_errors() {
+        let pipeline = PipelineBuilder::new("p")
+            .script("a", "echo a")
+            .then()
+            .script("b", "echo b")
+            .depends_on("a")
+            .when("script == 'Success from step 1'")
+            .then()
+            .script("c", "echo c")
+            .when("a ==")
+            .then()
+            .build();
+
+        assert!(pipeline.validate().is_ok());
+        let warnings: Vec<_> = pipeline
+            .warnings()
+            .into_iter()
+            .map(|w| (w.step.unwrap(), w.kind))
+            .collect();
+        assert!(matches!(
+            &warnings[..],
+            [
+                (b, ValidationErrorKind::UnknownReference(r)),
+                (c, ValidationErrorKind::InvalidExpression(_)),
+            ] if b == "b" && r == "script" && c == "c"
+        ));
+    }
+
+    #[test]
+    fn registry_credentials_must_name_a_secret() {
+        let credentials =
+            crate::container::RegistryCredentials::new("r.example.com", "ci", "hunter2 ");
+        let builder = PipelineBuilder::new("p")
+            .container("build", "r.example.com/app")
+            .registry_credentials(credentials)
+            .then();
+
+        assert_eq!(
+            kinds(builder),
+            [ValidationErrorKind::InvalidSecretName(
+                "hunter2 ".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_llm_data_that_does_not_deserialize() {
+        let mut pipeline = PipelineBuilder::new("p").llm_workflow("ask").then().build();
+        pipeline.steps[0].data = serde_json::json!({ "prompt": "hi", "temperature": "warm" });
+
+        let errors = pipeline.validate().unwrap_err();
+
+        let kinds: Vec<_> = errors.iter().map(|e| (e.field.as_str(), &e.kind)).collect();
+        assert!(matches!(
+            kinds[..],
+            [("data", ValidationErrorKind::InvalidStepData(_))]
+        ));
+    }
+
+    #[test]
+    fn reports_container_data_that_does_not_deserialize() {
+        let mut pipeline = PipelineBuilder::new("p")
+            .container("build", "rust")
+            .then()
+            .build();
+        pipeline.steps[0].data["volumes"] = serde_json::json!([{ "container_path": "/work" }]);
+
+        let errors = pipeline.validate().unwrap_err();
+
+        let kinds: Vec<_> = errors.iter().map(|e| (e.field.as_str(), &e.kind)).collect();
+        assert!(matches!(
+            kinds[..],
+            [("data", ValidationErrorKind::InvalidStepData(_))]
+        ));
+    }
+
+    #[test]
+    fn reports_unknown_tool_bindings_explicitly() {
+        let mut pipeline = PipelineBuilder::new("p").llm_workflow("ask").then().build();
+        pipeline.steps[0].data = serde_json::json!({
+            "prompt": "hi",
+            "tools": [{
+                "name": "lookup",
+                "description": "Looks things up",
+                "parameters": { "type": "object" },
+                "binding": { "type": "grpc", "url": "grpc://lookup" },
+            }],
+        });
+
+        let errors = pipeline.validate().unwrap_err();
+
+        let kinds: Vec<_> = errors.iter().map(|e| (e.field.as_str(), &e.kind)).collect();
+        assert!(matches!(
+            kinds[..],
+            [("tools.lookup.binding", ValidationErrorKind::UnknownToolBinding(t))] if t == "grpc"
+        ));
+    }
+
+    fn fields(builder: PipelineBuilder) -> Vec<(String, ValidationErrorKind)> {
+        match builder.try_build() {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| (e.field, e.kind)).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_malformed_triggers() {
+        let builder = PipelineBuilder::new("scheduled")
+            .trigger(crate::Trigger::cron("0 25 * * *"))
+            .script("only", "echo hi")
+            .then();
+
+        let found = fields(builder);
+
+        assert!(matches!(
+            &found[..],
+            [(field, ValidationErrorKind::InvalidTrigger(_))] if field == "trigger"
+        ));
+    }
+
+    #[test]
+    fn expression_warnings_flag_steps_that_are_not_upstream() {
+        let pipeline = PipelineBuilder::new("conditional")
+            .script("first", "echo 1")
+            .then()
+            .script("second", "echo 2")
+            .depends_on("first")
+            .when(crate::expression::Expr::output("first").eq(1))
+            .then()
+            .script("third", "echo 3")
+            .when("second.ok")
+            .then()
+            .build();
+
+        let warnings: Vec<_> = pipeline.warnings().into_iter().map(|w| w.kind).collect();
+
+        assert_eq!(
+            warnings,
+            [ValidationErrorKind::NonUpstreamReference(
+                "second".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_prompt_references_to_unknown_and_non_upstream_steps() {
+        let builder = PipelineBuilder::new("llm")
+            .script("weather", "echo sunny")
+            .then()
+            .script("traffic", "echo busy")
+            .then()
+            .llm_workflow("advice")
+            .prompt("{{nested_json 'weather'}} {{nested_json 'traffic'}} {{nested_json 'news'}}")
+            .depends_on("weather")
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                (
+                    "prompt".to_string(),
+                    ValidationErrorKind::NonUpstreamReference("traffic".to_string())
+                ),
+                (
+                    "prompt".to_string(),
+                    ValidationErrorKind::UnknownReference("news".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_references_in_system_prompts_instructions_and_conditions() {
+        let builder = PipelineBuilder::new("support")
+            .script("ticket", "cat ticket.json")
+            .then()
+            .orchestrator("plan")
+            .system_prompt("Policy: {{nested_json 'policy'}}")
+            .worker(Worker::new("writer", "Answer {{nested_json 'ticket'}}"))
+            .then()
+            .routing("triage")
+            .depends_on("ticket")
+            .route("{{nested_json 'plan'}} mentions a refund", "refund")
+            .then()
+            .script("refund", "echo refund")
+            .depends_on("triage")
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                (
+                    "system_prompt".to_string(),
+                    ValidationErrorKind::UnknownReference("policy".to_string())
+                ),
+                (
+                    "workers.writer.instructions".to_string(),
+                    ValidationErrorKind::NonUpstreamReference("ticket".to_string())
+                ),
+                (
+                    "routes.refund.condition".to_string(),
+                    ValidationErrorKind::NonUpstreamReference("plan".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_malformed_prompt_templates() {
+        let builder = PipelineBuilder::new("llm")
+            .llm_workflow("advice")
+            .prompt("{{nested_json weather}}")
+            .then();
+
+        assert!(matches!(
+            &kinds(builder)[..],
+            [ValidationErrorKind::InvalidTemplate(_)]
+        ));
+    }
+
+    #[test]
+    fn read_only_volumes_may_be_shared_and_ordered_steps_may_write() {
+        let builder = PipelineBuilder::new("artifacts")
+            .container("write", "alpine")
+            .named_volume("artifacts", "/out")
+            .mount(VolumeMount::host("/etc/ssl", "/certs").read_only())
+            .then()
+            .container("read", "alpine")
+            .depends_on("write")
+            .mount(VolumeMount::named("artifacts", "/in").read_only())
+            .then()
+            .container("audit", "alpine")
+            .mount(VolumeMount::host("/etc/ssl", "/certs").read_only())
+            .scratch_volume("/out")
+            .then();
+
+        assert_eq!(kinds(builder), []);
+    }
+
+    #[test]
+    fn reports_duplicate_mount_paths_and_concurrent_writers() {
+        let builder = PipelineBuilder::new("conflicts")
+            .container("a", "alpine")
+            .named_volume("shared", "/data")
+            .then()
+            .container("b", "alpine")
+            .mount(VolumeMount::named("shared", "/data").read_only())
+            .scratch_volume("/data")
+            .then();
+
+        let found = kinds(builder);
+
+        assert!(matches!(
+            &found[..],
+            [
+                ValidationErrorKind::DuplicateMountPath(path),
+                ValidationErrorKind::ConflictingMount { other_step, .. },
+            ] if path == "/data" && other_step == "a"
+        ));
+    }
+
+    #[test]
+    fn reports_llm_settings_out_of_range() {
+        let builder = PipelineBuilder::new("llm")
+            .llm_workflow("answer")
+            .model(" ")
+            .temperature(2.5)
+            .top_p(0.0)
+            .max_tokens(0)
+            .stop_sequence("")
+            .then();
+
+        let found: Vec<_> = fields(builder).into_iter().map(|(f, _)| f).collect();
+
+        assert_eq!(
+            found,
+            [
+                "temperature",
+                "top_p",
+                "max_tokens",
+                "model",
+                "stop_sequences"
+            ]
+        );
+    }
+
+    #[test]
+    fn temperature_errors_name_the_allowed_range() {
+        let builder = PipelineBuilder::new("llm")
+            .llm_workflow("answer")
+            .temperature(2.5)
+            .then();
+
+        assert_eq!(
+            kinds(builder),
+            [ValidationErrorKind::OutOfRange {
+                value: "2.5".to_string(),
+                expected: "0.0..=2.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn accepts_llm_settings_at_the_range_bounds() {
+        let builder = PipelineBuilder::new("llm")
+            .llm_workflow("answer")
+            .temperature(2.0)
+            .top_p(1.0)
+            .max_tokens(1)
+            .then();
+
+        assert_eq!(kinds(builder), []);
+    }
+
+    #[test]
+    fn tools_require_an_agent_workflow() {
+        let tool = Tool::webhook("lookup", "Look up", "https://a");
+        let builder = PipelineBuilder::new("support")
+            .llm_workflow("chat")
+            .tool(tool.clone())
+            .then()
+            .llm_workflow("agent")
+            .workflow_type(&crate::dsl::LLMWorkflowTypes::Agent)
+            .tool(tool)
+            .then();
+
+        assert_eq!(
+            kinds(builder),
+            [ValidationErrorKind::ToolsRequireAgent(
+                "generic".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_tool_definitions() {
+        let builder = PipelineBuilder::new("support")
+            .llm_workflow("agent")
+            .workflow_type(&crate::dsl::LLMWorkflowTypes::Agent)
+            .tool(
+                Tool::webhook("look up", "", "")
+                    .parameters(serde_json::json!({ "type": "string" })),
+            )
+            .tool(Tool::container("resize", "Resize", ""))
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                (
+                    "tools".to_string(),
+                    ValidationErrorKind::InvalidToolName("look up".to_string())
+                ),
+                (
+                    "tools.look up.description".to_string(),
+                    ValidationErrorKind::EmptyValue
+                ),
+                (
+                    "tools.look up.parameters".to_string(),
+                    ValidationErrorKind::InvalidToolParameters
+                ),
+                (
+                    "tools.look up.url".to_string(),
+                    ValidationErrorKind::EmptyValue
+                ),
+                (
+                    "tools.resize.image".to_string(),
+                    ValidationErrorKind::EmptyValue
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_must_target_downstream_steps() {
+        let builder = PipelineBuilder::new("support")
+            .routing("triage")
+            .route("", "refund")
+            .route("a bug", "missing")
+            .default_route("bug")
+            .then()
+            .script("refund", "echo refund")
+            .then()
+            .script("bug", "echo bug")
+            .depends_on("triage")
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                (
+                    "routes".to_string(),
+                    ValidationErrorKind::RouteTargetNotDownstream("refund".to_string())
+                ),
+                (
+                    "routes".to_string(),
+                    ValidationErrorKind::UnknownReference("missing".to_string())
+                ),
+                (
+                    "routes.refund.condition".to_string(),
+                    ValidationErrorKind::EmptyValue
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn routing_steps_need_routes() {
+        let builder = PipelineBuilder::new("support").routing("triage").then();
+
+        assert_eq!(kinds(builder), [ValidationErrorKind::EmptyValue]);
+    }
+
+    #[test]
+    fn reports_missing_and_duplicate_workers() {
+        let builder = PipelineBuilder::new("support")
+            .orchestrator("empty")
+            .then()
+            .orchestrator("refund")
+            .worker(Worker::new("ledger", ""))
+            .worker(Worker::new("ledger", "Again"))
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                ("workers".to_string(), ValidationErrorKind::EmptyValue),
+                (
+                    "workers.ledger.instructions".to_string(),
+                    ValidationErrorKind::EmptyValue
+                ),
+                (
+                    "workers".to_string(),
+                    ValidationErrorKind::DuplicateWorker("ledger".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn prompt_chains_need_prompts() {
+        let builder = PipelineBuilder::new("support")
+            .prompt_chain("chain")
+            .chain(" ")
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [("prompts".to_string(), ValidationErrorKind::EmptyValue)]
+        );
+    }
+
+    #[test]
+    fn evaluator_optimizers_need_criteria_and_an_iteration_limit() {
+        let builder = PipelineBuilder::new("support")
+            .evaluator_optimizer("reply")
+            .then()
+            .evaluator_optimizer("again")
+            .criterion("polite")
+            .max_iterations(0)
+            .then();
+
+        assert_eq!(
+            fields(builder),
+            [
+                ("criteria".to_string(), ValidationErrorKind::EmptyValue),
+                (
+                    "max_iterations".to_string(),
+                    ValidationErrorKind::MissingValue
+                ),
+                (
+                    "max_iterations".to_string(),
+                    ValidationErrorKind::OutOfRange {
+                        value: "0".to_string(),
+                        expected: "1..".to_string(),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unsendable_webhook_settings() {
+        let builder = PipelineBuilder::new("hooks")
+            .webhook("bad", "ftp://example.com")
+            .body(&serde_json::json!({ "a": 1 }))
+            .timeout(0)
+            .expect_status(700)
+            .then();
+
+        assert_eq!(
+            kinds(builder),
+            [
+                ValidationErrorKind::InvalidUrl("ftp://example.com".to_string()),
+                ValidationErrorKind::BodyNotAllowed(HttpMethod::Get),
+                ValidationErrorKind::OutOfRange {
+                    value: "0".to_string(),
+                    expected: "1..".to_string(),
+                },
+                ValidationErrorKind::OutOfRange {
+                    value: "700".to_string(),
+                    expected: "100..=599".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn templated_webhook_urls_are_accepted() {
+        let builder = PipelineBuilder::new("hooks")
+            .webhook("call", "{{ input.url }}")
+            .then();
+
+        assert_eq!(kinds(builder), []);
+    }
+
+    #[test]
+    fn webhook_auth_needs_a_secret_name_and_no_authorization_header() {
+        let builder = PipelineBuilder::new("hooks")
+            .webhook("call", "https://example.com")
+            .bearer_auth("not a secret")
+            .header("Authorization", "Bearer literal")
+            .then();
+
+        assert_eq!(
+            kinds(builder),
+            [
+                ValidationErrorKind::InvalidSecretName("not a secret".to_string()),
+                ValidationErrorKind::ConflictingAuthorization,
+            ]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Typed configuration for `webhook` steps.
+//!
+//! [`WebhookSpec`] mirrors the step's `data` object, so it can be read from
+//! and written back to an existing [`Step`](crate::dsl::Step) without losing
+//! keys it does not model.
+//!
+//! Credentials are never stored in the step: [`WebhookAuth`] names secrets
+//! the server resolves when it sends the request.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// HTTP method of a webhook request. Serialized in upper case; parsing is
+/// case-insensitive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+    Options,
+}
+
+impl HttpMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+        }
+    }
+
+    /// Whether requests with this method may carry a body.
+    pub fn allows_body(self) -> bool {
+        !matches!(self, HttpMethod::Get | HttpMethod::Head)
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_uppercase().as_str() {
+            "GET" => HttpMethod::Get,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "PATCH" => HttpMethod::Patch,
+            "DELETE" => HttpMethod::Delete,
+            "HEAD" => HttpMethod::Head,
+            "OPTIONS" => HttpMethod::Options,
+            _ => return Err(format!("unknown HTTP method `{}`", s)),
+        })
+    }
+}
+
+impl TryFrom<String> for HttpMethod {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<HttpMethod> for String {
+    fn from(method: HttpMethod) -> Self {
+        method.as_str().to_string()
+    }
+}
+
+/// How `body` is sent. JSON, the default, serializes the value; the other
+/// formats send a string body as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyFormat {
+    Json,
+    /// `application/x-www-form-urlencoded`, already encoded by the builder.
+    Form,
+    Text,
+}
+
+/// Request authentication, resolved from named server-side secrets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookAuth {
+    /// `Authorization: Bearer <token>`, with the token read from secret
+    /// `token_secret`.
+    Bearer { token_secret: String },
+    /// HTTP basic authentication, with the password read from secret
+    /// `password_secret`.
+    Basic {
+        username: String,
+        password_secret: String,
+    },
+}
+
+impl WebhookAuth {
+    /// Name of the secret this authentication reads.
+    pub fn secret(&self) -> &str {
+        match self {
+            WebhookAuth::Bearer { token_secret } => token_secret,
+            WebhookAuth::Basic {
+                password_secret, ..
+            } => password_secret,
+        }
+    }
+}
+
+/// The `data` of a `webhook` step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebhookSpec {
+    /// Target URL, including any encoded query string.
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_format: Option<BodyFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<WebhookAuth>,
+    /// Timeout in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Statuses that count as success; any 2xx status when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected_status: Vec<u16>,
+    /// Keys not modelled above, kept as-is.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl WebhookSpec {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Reads a spec from a step's `data`.
+    pub fn from_data(data: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(data)
+    }
+
+    /// The `data` object for this spec.
+    pub fn to_data(&self) -> Value {
+        serde_json::to_value(self).expect("webhook spec serializes to JSON")
+    }
+
+    /// Whether `status` counts as success.
+    pub fn accepts_status(&self, status: u16) -> bool {
+        if self.expected_status.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            self.expected_status.contains(&status)
+        }
+    }
+
+    /// The header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Appends `key=value` to the query string of `url`, percent-encoding
+    /// both, and keeping any `#fragment` last.
+    pub fn append_query(&mut self, key: &str, value: &str) {
+        let (base, fragment) = match self.url.split_once('#') {
+            Some((base, fragment)) => (base, Some(fragment)),
+            None => (self.url.as_str(), None),
+        };
+        let separator = if !base.contains('?') {
+            "?"
+        } else if base.ends_with('?') || base.ends_with('&') {
+            ""
+        } else {
+            "&"
+        };
+        let mut url = format!("{}{}{}", base, separator, encode_pair(key, value));
+        if let Some(fragment) = fragment {
+            url.push('#');
+            url.push_str(fragment);
+        }
+        self.url = url;
+    }
+}
+
+/// Encodes `fields` as an `application/x-www-form-urlencoded` body.
+pub fn encode_form(fields: &[(&str, &str)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| encode_pair(key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn encode_pair(key: &str, value: &str) -> String {
+    format!("{}={}", percent_encode(key), percent_encode(value))
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::PipelineBuilder;
+    use serde_json::json;
+
+    #[test]
+    fn methods_parse_case_insensitively_and_serialize_upper_case() {
+        assert_eq!("patch".parse::<HttpMethod>(), Ok(HttpMethod::Patch));
+        assert!("FETCH".parse::<HttpMethod>().is_err());
+        assert_eq!(
+            serde_json::to_value(HttpMethod::Delete).unwrap(),
+            json!("DELETE")
+        );
+        assert!(!HttpMethod::Head.allows_body());
+        assert!(HttpMethod::Post.allows_body());
+    }
+
+    #[test]
+    fn query_parameters_are_encoded_before_the_fragment() {
+        let mut spec = WebhookSpec::new("https://api.example.com/search#top");
+        spec.append_query("q", "rust & serde");
+        spec.append_query("page", "2");
+        assert_eq!(
+            spec.url,
+            "https://api.example.com/search?q=rust%20%26%20serde&page=2#top"
+        );
+
+        let mut open = WebhookSpec::new("https://api.example.com/search?");
+        open.append_query("q", "x");
+        assert_eq!(open.url, "https://api.example.com/search?q=x");
+    }
+
+    #[test]
+    fn forms_percent_encode_reserved_characters() {
+        assert_eq!(
+            encode_form(&[("user", "ops team"), ("next", "/home?x=1")]),
+            "user=ops%20team&next=%2Fhome%3Fx%3D1"
+        );
+    }
+
+    #[test]
+    fn headers_are_looked_up_case_insensitively() {
+        let mut spec = WebhookSpec::new("https://x");
+        spec.headers
+            .insert("Content-Type".to_string(), "text/markdown".to_string());
+        assert_eq!(spec.header("content-type"), Some("text/markdown"));
+    }
+
+    #[test]
+    fn any_2xx_is_accepted_unless_statuses_are_listed() {
+        let mut spec = WebhookSpec::new("https://x");
+        assert!(spec.accepts_status(204));
+        assert!(!spec.accepts_status(404));
+        spec.expected_status = vec![200, 404];
+        assert!(spec.accepts_status(404));
+        assert!(!spec.accepts_status(204));
+    }
+
+    #[test]
+    fn builder_options_serialize_into_step_data() {
+        let pipeline = PipelineBuilder::new("hooks")
+            .webhook("search", "https://api.example.com/search")
+            .query("q", "rust")
+            .bearer_auth("search_token")
+            .timeout(10)
+            .expect_status(200)
+            .expect_status(404)
+            .then()
+            .build();
+
+        assert_eq!(
+            pipeline.steps[0].data,
+            json!({
+                "url": "https://api.example.com/search?q=rust",
+                "method": "GET",
+                "auth": { "type": "bearer", "token_secret": "search_token" },
+                "timeout": 10,
+                "expected_status": [200, 404],
+            })
+        );
+    }
+
+    #[test]
+    fn form_bodies_set_their_content_type() {
+        let pipeline = PipelineBuilder::new("hooks")
+            .webhook("login", "https://api.example.com/login")
+            .method(HttpMethod::Post)
+            .form(&[("user", "ops")])
+            .basic_auth("ops", "login_password")
+            .then()
+            .build();
+
+        let spec = WebhookSpec::from_data(&pipeline.steps[0].data).unwrap();
+        assert_eq!(spec.body, Some(json!("user=ops")));
+        assert_eq!(spec.body_format, Some(BodyFormat::Form));
+        assert_eq!(
+            spec.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            spec.auth,
+            Some(WebhookAuth::Basic {
+                username: "ops".to_string(),
+                password_secret: "login_password".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn text_bodies_keep_an_explicit_content_type() {
+        let pipeline = PipelineBuilder::new("hooks")
+            .webhook("note", "https://api.example.com/notes")
+            .method(HttpMethod::Put)
+            .header("Content-Type", "text/markdown")
+            .text("# Deployed")
+            .then()
+            .build();
+
+        let spec = WebhookSpec::from_data(&pipeline.steps[0].data).unwrap();
+        assert_eq!(spec.headers.len(), 1);
+        assert_eq!(spec.header("content-type"), Some("text/markdown"));
+        assert_eq!(spec.body, Some(json!("# Deployed")));
+        assert_eq!(spec.body_format, Some(BodyFormat::Text));
+    }
+}
@@ -0,0 +1,139 @@
+use std::process::Output;
+
+use helios_client::testing::{MockFailure, MockHeliosServer, StepResponse};
+use serde_json::json;
+use tokio::process::Command;
+
+const REPORT: &str = "tests/fixtures/report.yaml";
+const CYCLE: &str = "tests/fixtures/cycle.yaml";
+
+/// Runs the `helios` binary against `endpoint` with retries disabled.
+async fn helios(endpoint: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_helios"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--endpoint", endpoint, "--attempts", "1"])
+        .args(args)
+        .env_remove("HELIOS_TOKEN")
+        .env_remove("HELIOS_API_KEY")
+        .output()
+        .await
+        .expect("failed to run helios")
+}
+
+/// An endpoint nothing listens on, for commands that must stay offline.
+const OFFLINE: &str = "http://127.0.0.1:9/graphql";
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn validate_accepts_valid_files_offline() {
+    let output = helios(OFFLINE, &["validate", REPORT]).await;
+
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).contains("report.yaml: ok"));
+}
+
+#[tokio::test]
+async fn validate_exits_2_for_invalid_files() {
+    let output = helios(OFFLINE, &["validate", REPORT, CYCLE]).await;
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stdout(&output).contains("report.yaml: ok"));
+    assert!(stderr(&output).contains("cycle.yaml"));
+}
+
+#[tokio::test]
+async fn validate_exits_2_for_unreadable_files() {
+    let output = helios(OFFLINE, &["validate", "tests/fixtures/missing.yaml"]).await;
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn graph_prints_dependencies_offline() {
+    let output = helios(OFFLINE, &["graph", REPORT]).await;
+
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let graph = stdout(&output);
+    assert!(graph.contains("summarize (script) [output]"), "{}", graph);
+    assert!(graph.contains("<- fetch"), "{}", graph);
+
+    let dot = stdout(&helios(OFFLINE, &["graph", REPORT, "--format", "dot"]).await);
+    assert!(dot.contains("digraph"), "{}", dot);
+}
+
+#[tokio::test]
+async fn graph_exits_2_for_invalid_files() {
+    let output = helios(OFFLINE, &["graph", CYCLE]).await;
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn run_prints_outputs_and_exits_0() {
+    let server = MockHeliosServer::start().await;
+    server.on_step("summarize", StepResponse::ok(json!("done")));
+
+    let output = helios(&server.endpoint(), &["run", REPORT, "--output", "raw"]).await;
+
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).lines().any(|line| line == "done"));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn run_exits_1_when_a_step_fails() {
+    let server = MockHeliosServer::start().await;
+    server.on_step("summarize", StepResponse::error("boom"));
+
+    let output = helios(&server.endpoint(), &["run", REPORT, "--output", "json"]).await;
+
+    assert_eq!(output.status.code(), Some(1));
+    let printed: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(printed["outputs"][0]["error"], "boom");
+}
+
+#[tokio::test]
+async fn run_exits_2_without_contacting_the_server_for_invalid_files() {
+    let server = MockHeliosServer::start().await;
+
+    let output = helios(&server.endpoint(), &["run", CYCLE]).await;
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn run_exits_3_on_server_errors() {
+    let server = MockHeliosServer::start().await;
+    server.fail_next(MockFailure::status(500));
+
+    let output = helios(&server.endpoint(), &["run", REPORT]).await;
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).starts_with("error:"), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn ping_reports_the_hello_message() {
+    let server = MockHeliosServer::start().await;
+    server.set_hello("hi there");
+
+    let output = helios(&server.endpoint(), &["ping"]).await;
+
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).contains("is reachable: hi there"));
+}
+
+#[tokio::test]
+async fn ping_exits_3_when_the_server_is_unreachable() {
+    let output = helios(OFFLINE, &["ping", "--timeout", "5"]).await;
+
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use helios_client::dsl::PipelineBuilder;
+use helios_client::testing::{MockFailure, MockHeliosServer, StepResponse};
+use helios_client::{
+    HeliosClientError, HeliosGraphQLClient, RetryPolicy, Trigger, IDEMPOTENCY_KEY_HEADER,
+};
+use serde_json::json;
+
+fn two_step_pipeline() -> helios_client::dsl::Pipeline {
+    PipelineBuilder::new("two-steps")
+        .script("first", "echo one")
+        .then()
+        .script("second", "echo two")
+        .depends_on("first")
+        .then()
+        .output("first")
+        .output("second")
+        .build()
+}
+
+#[tokio::test]
+async fn run_pipeline_returns_programmed_outputs() {
+    let server = MockHeliosServer::start().await;
+    server.on_step("first", StepResponse::ok(json!({ "n": 1 })));
+    server.on_step("second", StepResponse::error("boom"));
+
+    let response = server
+        .client()
+        .run_pipeline(two_step_pipeline())
+        .await
+        .unwrap();
+
+    assert!(!response.success);
+    assert_eq!(response.outputs.len(), 2);
+    assert_eq!(response.outputs[0].result_json, json!({ "n": 1 }));
+    assert_eq!(response.outputs[1].error.as_deref(), Some("boom"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].operation_name.as_deref(), Some("RunPipeline"));
+    assert_eq!(
+        requests[0].pipeline().unwrap()["steps"][1]["dependsOn"],
+        "first"
+    );
+}
+
+#[tokio::test]
+async fn create_pipeline_sends_stable_idempotency_key() {
+    let server = MockHeliosServer::start().await;
+    let pipeline = PipelineBuilder::new("scheduled")
+        .trigger(Trigger::manual())
+        .script("only", "echo hi")
+        .then()
+        .build();
+
+    let client = server.client();
+    let created = client.create_pipeline(pipeline.clone()).await.unwrap();
+    client.create_pipeline(pipeline).await.unwrap();
+
+    assert_eq!(created.id, "pipeline-1");
+    assert_eq!(created.trigger, "manual");
+    let requests = server.requests();
+    let key = |i: usize| requests[i].headers[&IDEMPOTENCY_KEY_HEADER.to_lowercase()].clone();
+    assert_eq!(key(0), key(1));
+}
+
+#[tokio::test]
+async fn same_pipeline_can_be_run_and_created() {
+    let server = MockHeliosServer::start().await;
+    let pipeline = PipelineBuilder::new("both")
+        .trigger(Trigger::manual())
+        .script("only", "echo hi")
+        .then()
+        .build();
+
+    let client = server.client();
+    client.run_pipeline(pipeline.clone()).await.unwrap();
+    client.create_pipeline(pipeline).await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].pipeline().unwrap()["steps"][0]["id"], "only");
+    assert_eq!(requests[1].pipeline().unwrap()["steps"][0]["name"], "only");
+    assert!(requests[0].pipeline().unwrap().get("trigger").is_none());
+}
+
+#[tokio::test]
+async fn hello_reaches_server() {
+    let server = MockHeliosServer::start().await;
+    server.set_hello("hi there");
+
+    assert_eq!(server.client().hello().await.unwrap(), "hi there");
+}
+
+#[tokio::test]
+async fn http_and_graphql_failures_are_structured() {
+    let server = MockHeliosServer::start().await;
+    server.fail_next(MockFailure::status(400));
+    server.fail_next(MockFailure::graphql("bad input", Some("BAD_USER_INPUT")));
+    let client = server.client();
+
+    let err = client.run_pipeline(two_step_pipeline()).await.unwrap_err();
+    assert!(matches!(err, HeliosClientError::HttpStatus { status, .. } if status == 400));
+    assert!(err.is_client_error());
+    assert!(!err.is_retryable());
+
+    let err = client.run_pipeline(two_step_pipeline()).await.unwrap_err();
+    let HeliosClientError::GraphQL(errors) = &err else {
+        panic!("expected GraphQL error, got {err:?}");
+    };
+    assert!(errors.has_code("BAD_USER_INPUT"));
+    assert!(err.is_client_error());
+}
+
+#[tokio::test]
+async fn retry_policy_retries_transient_statuses() {
+    let server = MockHeliosServer::start().await;
+    server.fail_next(MockFailure::status(503));
+    server.fail_next(MockFailure::status(502));
+    let client = HeliosGraphQLClient::builder(&server.endpoint())
+        .retry_policy(
+            RetryPolicy::new()
+                .max_attempts(3)
+                .initial_backoff(Duration::from_millis(1)),
+        )
+        .build()
+        .unwrap();
+
+    let response = client.run_pipeline(two_step_pipeline()).await.unwrap();
+
+    assert!(response.success);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn run_pipeline_retries_share_one_idempotency_key_per_call() {
+    let server = MockHeliosServer::start().await;
+    server.fail_next(MockFailure::status(503));
+    let client = HeliosGraphQLClient::builder(&server.endpoint())
+        .retry_policy(
+            RetryPolicy::new()
+                .max_attempts(2)
+                .initial_backoff(Duration::from_millis(1)),
+        )
+        .build()
+        .unwrap();
+
+    client.run_pipeline(two_step_pipeline()).await.unwrap();
+    client.run_pipeline(two_step_pipeline()).await.unwrap();
+
+    let requests = server.requests();
+    let key = |i: usize| requests[i].headers[&IDEMPOTENCY_KEY_HEADER.to_lowercase()].clone();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(key(0), key(1));
+    assert_ne!(key(1), key(2));
+}
+
+#[tokio::test]
+async fn invalid_pipelines_are_rejected_before_sending() {
+    let server = MockHeliosServer::start().await;
+    let pipeline = PipelineBuilder::new("broken")
+        .script("only", "echo hi")
+        .depends_on("missing")
+        .then()
+        .build();
+
+    let err = server.client().run_pipeline(pipeline).await.unwrap_err();
+
+    assert!(matches!(err, HeliosClientError::InvalidPipeline(_)));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn builder_sends_auth_and_default_headers() {
+    let server = MockHeliosServer::start().await;
+    let client = HeliosGraphQLClient::builder(&server.endpoint())
+        .bearer_token("secret")
+        .header("x-team", "platform")
+        .build()
+        .unwrap();
+
+    client.hello().await.unwrap();
+
+    let headers = &server.requests()[0].headers;
+    assert_eq!(headers["authorization"], "Bearer secret");
+    assert_eq!(headers["x-team"], "platform");
+}
+
+#[tokio::test]
+async fn pipeline_run_gives_typed_access_to_outputs() {
+    #[derive(serde::Deserialize, PartialEq, Debug)]
+    struct Count {
+        n: u32,
+    }
+
+    let server = MockHeliosServer::start().await;
+    server.on_step("first", StepResponse::ok(json!({ "n": 1 })));
+    server.on_step("second", StepResponse::error("boom"));
+
+    let run = server
+        .client()
+        .run_pipeline(two_step_pipeline())
+        .await
+        .unwrap();
+
+    assert_eq!(run.output_as::<Count>("first").unwrap(), Count { n: 1 });
+    assert!(matches!(
+        run.output_as::<Count>("missing"),
+        Err(HeliosClientError::MissingOutput(id)) if id == "missing"
+    ));
+    assert_eq!(run.failed_steps().collect::<Vec<_>>(), ["second"]);
+    assert_eq!(run.errors()[0].error, "boom");
+
+    let HeliosClientError::PipelineFailed { step_errors, .. } = run.into_result().unwrap_err()
+    else {
+        panic!("expected PipelineFailed");
+    };
+    assert_eq!(step_errors.len(), 1);
+}
+
+#[tokio::test]
+async fn llm_results_are_checked_against_output_schemas() {
+    #[derive(serde::Deserialize, schemars::JsonSchema, PartialEq, Debug)]
+    struct Verdict {
+        label: String,
+        score: f64,
+    }
+
+    let server = MockHeliosServer::start().await;
+    server.on_step(
+        "good",
+        StepResponse::ok(json!({ "label": "spam", "score": 0.9 })),
+    );
+    server.on_step(
+        "bad",
+        StepResponse::ok(json!({ "label": "spam", "score": "high" })),
+    );
+    let pipeline = PipelineBuilder::new("classify")
+        .llm_workflow("good")
+        .prompt("Classify the message")
+        .output_schema::<Verdict>()
+        .then()
+        .llm_workflow("bad")
+        .prompt("Classify the message")
+        .output_schema_value(json!({
+            "type": "object",
+            "required": ["label", "score"],
+            "properties": { "score": { "type": "number", "maximum": 1 } }
+        }))
+        .then()
+        .output("good")
+        .output("bad")
+        .build();
+
+    let run = server.client().run_pipeline(pipeline).await.unwrap();
+
+    let sent = server.requests()[0].pipeline().unwrap()["steps"][0]["data"].clone();
+    assert_eq!(sent["output_schema"]["required"], json!(["label", "score"]));
+    assert_eq!(
+        run.output_as::<Verdict>("good").unwrap(),
+        Verdict {
+            label: "spam".to_string(),
+            score: 0.9
+        }
+    );
+    assert!(!run.is_success());
+    assert_eq!(run.failed_steps().collect::<Vec<_>>(), ["bad"]);
+    let violations = run.schema_violations("bad").unwrap();
+    assert_eq!(violations[0].path, "/score");
+    assert!(matches!(
+        run.output_as::<serde_json::Value>("bad"),
+        Err(HeliosClientError::OutputSchema { step_id, .. }) if step_id == "bad"
+    ));
+}
@@ -0,0 +1,172 @@
+use helios_client::dsl::{ActionType, PipelineBuilder};
+use helios_client::local::LocalExecutor;
+use helios_client::testing::MockHeliosServer;
+use helios_client::webhook::HttpMethod;
+use helios_client::HeliosClientError;
+use serde_json::json;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn runs_scripts_and_evaluates_conditions() {
+    let pipeline = PipelineBuilder::new("local")
+        .script("status", r#"echo '{"code": 200, "region": "eu"}'"#)
+        .then()
+        .llm_workflow("summary")
+        .prompt("Summarize {{nested_json 'status'}}")
+        .depends_on("status")
+        .when("status.code == 200 && status.region == 'eu'")
+        .then()
+        .script("alert", "echo paging")
+        .depends_on("status")
+        .when("status.code >= 500")
+        .then()
+        .script("escalate", "echo escalating")
+        .depends_on("alert")
+        .then()
+        .script("broken", "echo oops >&2; exit 3")
+        .then()
+        .script("after_broken", "echo unreachable")
+        .depends_on("broken")
+        .then()
+        .build();
+
+    let run = LocalExecutor::new()
+        .handler(ActionType::LlmWorkflow, |step, outputs| {
+            assert_eq!(step.effective_id(), "summary");
+            Ok(json!(format!("region {}", outputs["status"]["region"])))
+        })
+        .run(&pipeline)
+        .await
+        .unwrap();
+
+    assert!(!run.success);
+    assert_eq!(
+        run.output("status").unwrap().result_json,
+        json!({ "code": 200, "region": "eu" })
+    );
+    assert_eq!(run.output("summary").unwrap().result_json, "region \"eu\"");
+    assert!(run.output("alert").unwrap().result_json.is_null());
+    assert!(run.output("escalate").unwrap().error.is_none());
+    assert_eq!(
+        run.failed_steps().collect::<Vec<_>>(),
+        ["broken", "after_broken"]
+    );
+    assert!(run
+        .output("broken")
+        .unwrap()
+        .error
+        .as_ref()
+        .unwrap()
+        .contains("oops"));
+}
+
+#[tokio::test]
+async fn sends_webhooks_and_requires_handlers_for_remote_steps() {
+    let server = MockHeliosServer::start().await;
+    server.set_hello("pong");
+    let pipeline = PipelineBuilder::new("local")
+        .webhook("ping", &server.endpoint())
+        .method(HttpMethod::Post)
+        .header("x-probe", "1")
+        .body(&json!({ "query": "{ hello }" }))
+        .then()
+        .container("build", "rust:latest")
+        .depends_on("ping")
+        .then()
+        .output("ping")
+        .output("build")
+        .build();
+
+    let run = LocalExecutor::new().run(&pipeline).await.unwrap();
+
+    assert_eq!(
+        run.output("ping").unwrap().result_json,
+        json!({ "data": { "hello": "pong" } })
+    );
+    assert_eq!(server.requests()[0].headers["x-probe"], "1");
+    assert!(run.errors()[0].error.contains("no local handler"));
+}
+
+#[tokio::test]
+async fn webhooks_resolve_secrets_and_check_expected_statuses() {
+    let server = MockHeliosServer::start().await;
+    server.set_hello("pong");
+    let pipeline = PipelineBuilder::new("local")
+        .webhook("ping", &server.endpoint())
+        .method(HttpMethod::Post)
+        .query("q", "a b&c")
+        .bearer_auth("helios_token")
+        .timeout(5)
+        .body(&json!({ "query": "{ hello }" }))
+        .then()
+        .webhook("form", &server.endpoint())
+        .method(HttpMethod::Post)
+        .form(&[("name", "helios")])
+        .expect_status(415)
+        .then()
+        .webhook("unauthorized", &server.endpoint())
+        .basic_auth("admin", "missing_password")
+        .then()
+        .build();
+
+    let run = LocalExecutor::new()
+        .secret("helios_token", "tok")
+        .run(&pipeline)
+        .await
+        .unwrap();
+
+    assert_eq!(server.requests()[0].headers["authorization"], "Bearer tok");
+    assert_eq!(
+        run.output("ping").unwrap().result_json,
+        json!({ "data": { "hello": "pong" } })
+    );
+    assert!(run.output("form").unwrap().error.is_none());
+    assert_eq!(run.failed_steps().collect::<Vec<_>>(), ["unauthorized"]);
+    assert!(run.errors()[0].error.contains("secret `missing_password`"));
+}
+
+#[tokio::test]
+async fn invalid_pipelines_are_rejected_before_any_step_runs() {
+    let server = MockHeliosServer::start().await;
+    let pipeline = PipelineBuilder::new("local")
+        .webhook("ping", &server.endpoint())
+        .method(HttpMethod::Post)
+        .then()
+        .llm_workflow("summary")
+        .prompt("Summarize")
+        .temperature(9.0)
+        .depends_on("ping")
+        .then()
+        .build();
+
+    let result = LocalExecutor::new().run(&pipeline).await;
+
+    assert!(matches!(
+        result,
+        Err(HeliosClientError::InvalidPipeline(errors)) if errors.iter().any(|e| e.field == "temperature")
+    ));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn unparsable_conditions_are_rejected_before_any_step_runs() {
+    let server = MockHeliosServer::start().await;
+    let pipeline = PipelineBuilder::new("local")
+        .webhook("ping", &server.endpoint())
+        .method(HttpMethod::Post)
+        .then()
+        .script("after", "echo after")
+        .depends_on("ping")
+        .when("ping.code ==")
+        .then()
+        .build();
+    assert!(pipeline.validate().is_ok());
+
+    let result = LocalExecutor::new().run(&pipeline).await;
+
+    assert!(matches!(
+        result,
+        Err(HeliosClientError::InvalidConfig(message)) if message.starts_with("step `after`")
+    ));
+    assert!(server.requests().is_empty());
+}